@@ -0,0 +1,530 @@
+// Copyright (C) 2025 BastIsAwesome (bastisawesomeltd@gmail.com)
+//
+// This file is part of deque.
+//
+// deque is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// deque is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with deque.  If not, see <https://www.gnu.org/licenses/>.
+use std::fmt::Display;
+
+use crate::node::BinaryNode;
+use crate::render;
+use crate::trace::{NoObserver, TreeObserver};
+use crate::validate::{
+    self, Direction, InvariantViolation, TreeStats, ViolationKind,
+};
+
+type Link<T> = Option<Box<AaNode<T>>>;
+
+/// Andersson's AA tree.
+///
+/// Each node stores a level instead of a colour. Leaves are at level one, a
+/// left child is always one level below its parent, and a right child may
+/// share its parent's level but a right grandchild may not. Only two
+/// rebalancing steps are needed: `skew` rotates away a left child on the same
+/// level and `split` rotates away two right children on the same level.
+///
+/// Insertions and deletions report each step they take, `skew` and `split`
+/// rotations included, to the tree's [`TreeObserver`].
+pub struct AaTree<T: PartialEq + PartialOrd, O = NoObserver> {
+    root: Link<T>,
+    len: usize,
+    observer: O,
+}
+
+impl<T: PartialEq + PartialOrd> AaTree<T> {
+    pub fn new(data: Option<T>) -> Self {
+        let mut tree = Self::with_observer(NoObserver);
+        if let Some(data) = data {
+            tree.insert(data);
+        }
+        tree
+    }
+}
+
+impl<T: PartialEq + PartialOrd, O: TreeObserver<T>> AaTree<T, O> {
+    pub fn with_observer(observer: O) -> Self {
+        Self {
+            root: None,
+            len: 0,
+            observer,
+        }
+    }
+
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    pub fn insert(&mut self, value: T) {
+        if insert(&mut self.root, value, &mut self.observer) {
+            self.len += 1;
+        }
+    }
+
+    pub fn delete(&mut self, value: T) {
+        if delete(&mut self.root, &value, &mut self.observer) {
+            self.len -= 1;
+        }
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let mut node = self.root.as_deref();
+        while let Some(current) = node {
+            if current.data == value {
+                return true;
+            }
+
+            node = if value < current.data {
+                current.left.as_deref()
+            } else {
+                current.right.as_deref()
+            };
+        }
+
+        false
+    }
+
+    pub fn min(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        let mut node = self.root.as_deref()?;
+        while let Some(left) = &node.left {
+            node = left;
+        }
+        Some(node.data.clone())
+    }
+
+    pub fn max(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        let mut node = self.root.as_deref()?;
+        while let Some(right) = &node.right {
+            node = right;
+        }
+        Some(node.data.clone())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn size(&self) -> usize {
+        self.len
+    }
+
+    pub fn height(&self) -> usize {
+        validate::height(self.root.as_deref())
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+
+    /// Checks the search tree order, the stored size and the level rules.
+    pub fn validate(&self) -> Result<TreeStats, InvariantViolation> {
+        let Some(root) = &self.root else {
+            return Ok(TreeStats::default());
+        };
+
+        let stats = validate::check_order(root.as_ref())?;
+        validate::check_size(&[], self.len, stats.size)?;
+
+        check_levels(root, &mut Vec::new())?;
+        Ok(stats)
+    }
+}
+
+impl<T: PartialEq + PartialOrd + Display, O> AaTree<T, O> {
+    pub fn to_dot(&self) -> String {
+        render::to_dot("AaTree", self.root.as_deref())
+    }
+
+    pub fn render_ascii(&self) -> String {
+        render::render_ascii(self.root.as_deref())
+    }
+}
+
+/// Level of the node, a missing node being at level zero.
+fn level<T>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |node| node.level)
+}
+
+/// Rotates right if the left child is on the same level as the node.
+fn skew<T, O: TreeObserver<T>>(link: &mut Link<T>, observer: &mut O) {
+    let Some(node) = link else {
+        return;
+    };
+    if level(&node.left) != node.level {
+        return;
+    }
+
+    observer.on_rotate(Direction::Right, &node.data);
+    let mut node = link.take().unwrap();
+    let mut left = node.left.take().unwrap();
+    node.left = left.right.take();
+    left.right = Some(node);
+    *link = Some(left);
+}
+
+/// Rotates left and raises the new top a level if the right grandchild is
+/// on the same level as the node.
+fn split<T, O: TreeObserver<T>>(link: &mut Link<T>, observer: &mut O) {
+    let Some(node) = link else {
+        return;
+    };
+    let Some(right) = &node.right else {
+        return;
+    };
+    if level(&right.right) != node.level {
+        return;
+    }
+
+    observer.on_rotate(Direction::Left, &node.data);
+    let mut node = link.take().unwrap();
+    let mut right = node.right.take().unwrap();
+    node.right = right.left.take();
+    right.left = Some(node);
+    right.level += 1;
+    *link = Some(right);
+}
+
+/// Returns whether the value was added.
+fn insert<T: PartialOrd, O: TreeObserver<T>>(
+    link: &mut Link<T>,
+    value: T,
+    observer: &mut O,
+) -> bool {
+    let Some(node) = link else {
+        observer.on_link(&value);
+        *link = Some(Box::new(AaNode::new(value)));
+        return true;
+    };
+
+    observer.on_compare(&value, &node.data);
+    let inserted = if value < node.data {
+        observer.on_descend(Direction::Left);
+        insert(&mut node.left, value, observer)
+    } else if value > node.data {
+        observer.on_descend(Direction::Right);
+        insert(&mut node.right, value, observer)
+    } else {
+        return false;
+    };
+
+    skew(link, observer);
+    split(link, observer);
+    inserted
+}
+
+/// Returns whether the value was found and removed.
+fn delete<T: PartialOrd, O: TreeObserver<T>>(
+    link: &mut Link<T>,
+    value: &T,
+    observer: &mut O,
+) -> bool {
+    let Some(node) = link else {
+        // Value does not exist.
+        return false;
+    };
+
+    observer.on_compare(value, &node.data);
+    if *value < node.data {
+        observer.on_descend(Direction::Left);
+        if !delete(&mut node.left, value, observer) {
+            return false;
+        }
+    } else if *value > node.data {
+        observer.on_descend(Direction::Right);
+        if !delete(&mut node.right, value, observer) {
+            return false;
+        }
+    } else if node.left.is_none() && node.right.is_none() {
+        observer.on_unlink(&node.data);
+        *link = None;
+        return true;
+    } else if node.left.is_none() {
+        // Replace the value with its successor.
+        observer.on_unlink(&node.data);
+        observer.on_descend(Direction::Right);
+        node.data = take_min(&mut node.right, observer);
+    } else {
+        // Replace the value with its predecessor.
+        observer.on_unlink(&node.data);
+        observer.on_descend(Direction::Left);
+        node.data = take_max(&mut node.left, observer);
+    }
+
+    rebalance(link, observer);
+    true
+}
+
+fn take_min<T, O: TreeObserver<T>>(link: &mut Link<T>, observer: &mut O) -> T {
+    let node = link.as_mut().unwrap();
+    let min = match node.left {
+        Some(_) => {
+            observer.on_descend(Direction::Left);
+            take_min(&mut node.left, observer)
+        },
+        None => {
+            let mut node = link.take().unwrap();
+            *link = node.right.take();
+            return node.data;
+        },
+    };
+
+    rebalance(link, observer);
+    min
+}
+
+fn take_max<T, O: TreeObserver<T>>(link: &mut Link<T>, observer: &mut O) -> T {
+    let node = link.as_mut().unwrap();
+    let max = match node.right {
+        Some(_) => {
+            observer.on_descend(Direction::Right);
+            take_max(&mut node.right, observer)
+        },
+        None => {
+            let mut node = link.take().unwrap();
+            *link = node.left.take();
+            return node.data;
+        },
+    };
+
+    rebalance(link, observer);
+    max
+}
+
+/// Lowers the node if a removal below left it too high, then skews and
+/// splits along the right spine to restore the level rules.
+fn rebalance<T, O: TreeObserver<T>>(link: &mut Link<T>, observer: &mut O) {
+    let Some(node) = link else {
+        return;
+    };
+
+    let expected = level(&node.left).min(level(&node.right)) + 1;
+    if expected < node.level {
+        node.level = expected;
+        if let Some(right) = &mut node.right {
+            right.level = right.level.min(expected);
+        }
+    }
+
+    skew(link, observer);
+    let node = link.as_mut().unwrap();
+    skew(&mut node.right, observer);
+    if let Some(right) = &mut node.right {
+        skew(&mut right.right, observer);
+    }
+    split(link, observer);
+    split(&mut link.as_mut().unwrap().right, observer);
+}
+
+/// Checks every node's children and right grandchild against its level.
+fn check_levels<T>(
+    node: &AaNode<T>,
+    path: &mut Vec<Direction>,
+) -> Result<(), InvariantViolation> {
+    let fail = |path: &mut Vec<Direction>, steps: &[Direction], level| {
+        path.extend_from_slice(steps);
+        Err(InvariantViolation::new(
+            path.clone(),
+            ViolationKind::Level {
+                level,
+                parent: node.level,
+            },
+        ))
+    };
+
+    let left = level(&node.left);
+    if left + 1 != node.level {
+        return fail(path, &[Direction::Left], left);
+    }
+    let right = level(&node.right);
+    if right + 1 != node.level && right != node.level {
+        return fail(path, &[Direction::Right], right);
+    }
+    if let Some(right) = &node.right {
+        let grandchild = level(&right.right);
+        if grandchild >= node.level {
+            return fail(
+                path,
+                &[Direction::Right, Direction::Right],
+                grandchild,
+            );
+        }
+    }
+
+    for (direction, child) in [
+        (Direction::Left, &node.left),
+        (Direction::Right, &node.right),
+    ] {
+        let Some(child) = child else {
+            continue;
+        };
+
+        path.push(direction);
+        check_levels(child, path)?;
+        path.pop();
+    }
+
+    Ok(())
+}
+
+struct AaNode<T> {
+    data: T,
+    level: usize,
+    left: Link<T>,
+    right: Link<T>,
+}
+
+impl<T> AaNode<T> {
+    /// New nodes start out as leaves.
+    fn new(data: T) -> Self {
+        Self {
+            data,
+            level: 1,
+            left: None,
+            right: None,
+        }
+    }
+}
+
+impl<T> BinaryNode for AaNode<T> {
+    type Value = T;
+
+    fn value(&self) -> &T {
+        &self.data
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::conformance::{conformance_tests, empty, filled, impl_test_set};
+    use crate::trace::{TraceEvent, TraceRecorder};
+
+    impl_test_set!(AaTree, |_| AaTree::new(None), delete);
+    conformance_tests!(AaTree<usize>);
+
+    #[test]
+    fn test_insert() {
+        let mut tree: AaTree<usize> = empty();
+        tree.insert(16);
+        assert_eq!(tree.root.as_ref().unwrap().data, 16);
+
+        // The second value hangs right on the same level, the third splits
+        // the three into a new level.
+        tree.insert(32);
+        assert_eq!(tree.root.as_ref().unwrap().level, 1);
+        tree.insert(64);
+        assert_eq!(tree.root.as_ref().unwrap().data, 32);
+        assert_eq!(tree.root.as_ref().unwrap().level, 2);
+        assert!(tree.validate().is_ok());
+    }
+
+    #[test]
+    fn test_sorted_input_is_balanced() {
+        let mut tree: AaTree<usize> = empty();
+        for value in 0..1023 {
+            tree.insert(value);
+        }
+        // At most twice the height of a perfectly balanced tree.
+        assert!(tree.height() <= 20);
+        assert_eq!(tree.validate().unwrap().size, 1023);
+    }
+
+    #[test]
+    fn test_validate_empty() {
+        assert_eq!(
+            empty::<AaTree<usize>>().validate().unwrap(),
+            TreeStats::default()
+        );
+    }
+
+    #[test]
+    fn test_validate_level() {
+        // A leaf one level up from where it belongs.
+        let mut tree: AaTree<usize> = filled();
+        tree.root.as_mut().unwrap().left.as_mut().unwrap().level = 2;
+        let violation = tree.validate().unwrap_err();
+        assert_eq!(violation.path, vec![Direction::Left]);
+        assert_eq!(
+            violation.kind,
+            ViolationKind::Level {
+                level: 2,
+                parent: 2
+            }
+        );
+        assert_eq!(
+            violation.to_string(),
+            "root.left: level 2 is not allowed below a node at level 2"
+        );
+    }
+
+    #[test]
+    fn test_validate_missing_child() {
+        // A node above level one needs both children.
+        let mut tree: AaTree<usize> = filled();
+        tree.root.as_mut().unwrap().right = None;
+        tree.len = 2;
+        let violation = tree.validate().unwrap_err();
+        assert_eq!(violation.path, vec![Direction::Right]);
+        assert_eq!(
+            violation.kind,
+            ViolationKind::Level {
+                level: 0,
+                parent: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_observer_split() {
+        let mut tree = AaTree::with_observer(TraceRecorder::new());
+        tree.insert(1);
+        tree.insert(2);
+        tree.observer_mut().clear();
+
+        // Three nodes in a row on one level are split at the first.
+        tree.insert(3);
+        assert_eq!(
+            tree.observer().events().last(),
+            Some(&TraceEvent::Rotate {
+                direction: Direction::Left,
+                pivot: 1,
+            })
+        );
+        assert_eq!(
+            tree.observer()
+                .events()
+                .iter()
+                .filter(|event| matches!(event, TraceEvent::Rotate { .. }))
+                .count(),
+            1
+        );
+    }
+}
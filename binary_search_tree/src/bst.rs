@@ -14,50 +14,99 @@
 //
 // You should have received a copy of the GNU General Public License
 // along with deque.  If not, see <https://www.gnu.org/licenses/>.
+use std::fmt::Display;
+
+use crate::codec::{self, Codec, DecodeError};
+use crate::node::BinaryNode;
+use crate::render;
+use crate::trace::{NoObserver, TreeObserver};
+use crate::validate::{self, Direction, InvariantViolation, TreeStats};
+
 /// Duplicate values are ignored.
 /// Operations on values not in the tree are ignored.
-pub struct BinarySearchTree<T: PartialEq + PartialOrd> {
+///
+/// Insertions and deletions report each step they take to the tree's
+/// [`TreeObserver`], which does nothing unless one is installed with
+/// [`BinarySearchTree::with_observer`].
+pub struct BinarySearchTree<T: PartialEq + PartialOrd, O = NoObserver> {
     root: Option<Box<BstNode<T>>>,
+    len: usize,
+    observer: O,
 }
 
 impl<T: PartialEq + PartialOrd> BinarySearchTree<T> {
     pub fn new(data: Option<T>) -> Self {
-        match data {
-            Some(data) => Self {
-                root: Some(Box::new(BstNode::new(data))),
-            },
-            None => Self { root: None },
+        let mut bst = Self::with_observer(NoObserver);
+        if let Some(data) = data {
+            bst.insert(data);
         }
+        bst
+    }
+
+    /// Reads a tree written by [`BinarySearchTree::encode`], keeping its
+    /// exact shape. Bytes that describe an out of order tree are rejected.
+    pub fn decode<C: Codec<T>>(
+        bytes: &[u8],
+        codec: &C,
+    ) -> Result<Self, DecodeError> {
+        let (root, len) = codec::decode(bytes, codec)?;
+        Self::checked(root, len)
+    }
+
+    /// Rebuilds the tree with the given pre-order and in-order traversals.
+    /// Both must hold the same distinct values.
+    pub fn from_preorder_inorder(
+        preorder: &[T],
+        inorder: &[T],
+    ) -> Result<Self, DecodeError>
+    where
+        T: Clone,
+    {
+        let root = codec::from_preorder_inorder(preorder, inorder)?;
+        Self::checked(root, preorder.len())
+    }
+
+    fn checked(
+        root: Option<Box<BstNode<T>>>,
+        len: usize,
+    ) -> Result<Self, DecodeError> {
+        let bst = Self {
+            root,
+            len,
+            observer: NoObserver,
+        };
+        bst.validate().map_err(DecodeError::Invalid)?;
+        Ok(bst)
     }
+}
 
-    pub fn insert(&mut self, value: T) {
-        if self.root.is_none() {
-            self.root = Some(Box::new(BstNode::<T>::new(value)));
-            return;
+impl<T: PartialEq + PartialOrd, O: TreeObserver<T>> BinarySearchTree<T, O> {
+    pub fn with_observer(observer: O) -> Self {
+        Self {
+            root: None,
+            len: 0,
+            observer,
         }
+    }
 
-        insert(self.root.as_mut().unwrap(), value);
+    pub fn observer(&self) -> &O {
+        &self.observer
     }
 
-    pub fn delete(&mut self, value: T) {
-        if self.root.is_none() {
-            return;
-        }
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
 
-        if self.root.as_ref().unwrap().data == value {
-            if self.root.as_ref().unwrap().right.is_none() {
-                self.root = self.root.as_mut().unwrap().left.take();
-                return;
-            }
-            if self.root.as_ref().unwrap().left.is_none() {
-                self.root = self.root.as_mut().unwrap().right.take();
-                return;
-            }
-            self.root =
-                get_successor(self.root.as_mut().unwrap().left.take().unwrap());
+    pub fn insert(&mut self, value: T) {
+        if insert(&mut self.root, value, &mut self.observer) {
+            self.len += 1;
         }
+    }
 
-        delete(self.root.as_mut().unwrap(), value);
+    pub fn delete(&mut self, value: T) {
+        if delete(&mut self.root, value, &mut self.observer) {
+            self.len -= 1;
+        }
     }
 
     pub fn contains(&self, value: T) -> bool {
@@ -68,20 +117,18 @@ impl<T: PartialEq + PartialOrd> BinarySearchTree<T> {
         contains(self.root.as_ref().unwrap(), value)
     }
 
-    pub fn min(&self) -> Option<T> {
-        if self.root.is_none() {
-            return None;
-        }
-
-        min(self.root.as_ref().unwrap())
+    pub fn min(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        min(self.root.as_ref()?)
     }
 
-    pub fn max(&self) -> Option<T> {
-        if self.root.is_none() {
-            return None;
-        }
-
-        max(self.root.as_ref().unwrap())
+    pub fn max(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        max(self.root.as_ref()?)
     }
 
     pub fn is_empty(&self) -> bool {
@@ -89,114 +136,202 @@ impl<T: PartialEq + PartialOrd> BinarySearchTree<T> {
     }
 
     pub fn size(&self) -> usize {
-        if self.root.is_none() {
-            return 0;
-        }
-
-        size(self.root.as_ref().unwrap())
+        self.len
     }
 
     pub fn height(&self) -> usize {
-        if self.root.is_none() {
-            return 0;
-        }
-
-        height(self.root.as_ref().unwrap())
+        validate::height(self.root.as_deref())
     }
 
     pub fn clear(&mut self) {
         self.root = None;
+        self.len = 0;
     }
-}
 
-fn get_successor<T: PartialEq + PartialOrd>(
-    node: Box<BstNode<T>>,
-) -> Option<Box<BstNode<T>>> {
-    if node.right.is_none() {
-        return Some(node);
+    /// Writes the tree in a versioned, checksummed binary format that
+    /// records its shape as well as its values. See [`crate::codec`].
+    pub fn encode<C: Codec<T>>(&self, codec: &C) -> Vec<u8> {
+        codec::encode(self.root.as_deref(), self.len, codec)
     }
 
-    get_successor(node.right.unwrap())
+    /// Walks the whole tree checking that values are strictly ordered and
+    /// that the stored element count matches the nodes actually reachable.
+    pub fn validate(&self) -> Result<TreeStats, InvariantViolation> {
+        let stats = match &self.root {
+            Some(root) => validate::check_order(root.as_ref())?,
+            None => TreeStats::default(),
+        };
+
+        validate::check_size(&[], self.len, stats.size)?;
+
+        Ok(stats)
+    }
 }
 
-fn insert<T: PartialEq + PartialOrd>(node: &mut Box<BstNode<T>>, value: T) {
-    if node.data == value {
-        return;
+impl<T: PartialEq + PartialOrd + Display, O> BinarySearchTree<T, O> {
+    pub fn to_dot(&self) -> String {
+        render::to_dot("BinarySearchTree", self.root.as_deref())
     }
 
-    if value < node.data {
-        match &mut node.left {
-            Some(node) => insert(node, value),
-            None => node.left = Some(Box::new(BstNode::<T>::new(value))),
-        }
-    } else {
-        match &mut node.right {
-            Some(node) => insert(node, value),
-            None => node.right = Some(Box::new(BstNode::<T>::new(value))),
-        }
+    pub fn render_ascii(&self) -> String {
+        render::render_ascii(self.root.as_deref())
     }
 }
 
-/// Assumes the current node has already been checked and does not match.
-fn delete<T: PartialEq + PartialOrd>(node: &mut Box<BstNode<T>>, value: T) {
-    if value < node.data {
-        if node.left.is_none() {
-            // Value does not exist.
-            return;
-        }
-        if node.left.as_ref().unwrap().data == value {
-            node.left = None;
-            return;
+/// Detaches the largest node of the non-empty subtree, putting its left child
+/// in its place. The returned node has no children.
+fn take_max<T: PartialEq + PartialOrd, O: TreeObserver<T>>(
+    link: &mut Option<Box<BstNode<T>>>,
+    observer: &mut O,
+) -> Box<BstNode<T>> {
+    let mut link = link;
+    while link.as_ref().is_some_and(|node| node.right.is_some()) {
+        observer.on_descend(Direction::Right);
+        link = &mut link.as_mut().unwrap().right;
+    }
+
+    let mut max = link.take().unwrap();
+    *link = max.left.take();
+    max
+}
+
+/// Returns whether the value was added.
+fn insert<T: PartialEq + PartialOrd, O: TreeObserver<T>>(
+    link: &mut Option<Box<BstNode<T>>>,
+    value: T,
+    observer: &mut O,
+) -> bool {
+    let mut link = link;
+    while let Some(node) = link {
+        observer.on_compare(&value, &node.data);
+        if node.data == value {
+            return false;
         }
-        delete(node.left.as_mut().unwrap(), value);
-        return;
-    }
 
-    if node.right.is_none() {
-        // Value does not exist.
-        return;
+        link = if value < node.data {
+            observer.on_descend(Direction::Left);
+            &mut node.left
+        } else {
+            observer.on_descend(Direction::Right);
+            &mut node.right
+        };
     }
 
-    if node.right.as_ref().unwrap().data == value {
-        node.right = None;
-        return;
+    observer.on_link(&value);
+    *link = Some(Box::new(BstNode::<T>::new(value)));
+    true
+}
+
+/// Hibbard deletion. A node with two children is replaced by its in-order
+/// predecessor so that both subtrees are kept.
+/// Returns whether the value was found and removed.
+pub(crate) fn delete<T: PartialEq + PartialOrd, O: TreeObserver<T>>(
+    link: &mut Option<Box<BstNode<T>>>,
+    value: T,
+    observer: &mut O,
+) -> bool {
+    let mut link = link;
+    loop {
+        let Some(node) = link else {
+            // Value does not exist.
+            return false;
+        };
+
+        observer.on_compare(&value, &node.data);
+        if value == node.data {
+            break;
+        }
+
+        link = if value < node.data {
+            observer.on_descend(Direction::Left);
+            &mut link.as_mut().unwrap().left
+        } else {
+            observer.on_descend(Direction::Right);
+            &mut link.as_mut().unwrap().right
+        };
     }
 
-    delete(node.right.as_mut().unwrap(), value);
+    let mut node = link.take().unwrap();
+    observer.on_unlink(&node.data);
+    *link = match (node.left.take(), node.right.take()) {
+        (None, None) => None,
+        (Some(child), None) | (None, Some(child)) => Some(child),
+        (Some(left), Some(right)) => {
+            observer.on_descend(Direction::Left);
+            let mut left = Some(left);
+            let mut predecessor = take_max(&mut left, observer);
+            predecessor.left = left;
+            predecessor.right = Some(right);
+            Some(predecessor)
+        },
+    };
+
+    true
 }
 
 pub fn contains<T: PartialEq + PartialOrd>(
-    node: &Box<BstNode<T>>,
+    node: &BstNode<T>,
     value: T,
 ) -> bool {
-    todo!();
+    let mut node = Some(node);
+    while let Some(current) = node {
+        if current.data == value {
+            return true;
+        }
+
+        node = if value < current.data {
+            current.left.as_deref()
+        } else {
+            current.right.as_deref()
+        };
+    }
+
+    false
 }
 
-pub fn min<T: PartialEq + PartialOrd>(node: &Box<BstNode<T>>) -> Option<T> {
-    todo!();
+pub fn min<T: PartialEq + PartialOrd + Clone>(node: &BstNode<T>) -> Option<T> {
+    let mut node = node;
+    while let Some(left) = &node.left {
+        node = left;
+    }
+
+    Some(node.data.clone())
 }
 
-pub fn max<T: PartialEq + PartialOrd>(node: &Box<BstNode<T>>) -> Option<T> {
-    todo!();
+pub fn max<T: PartialEq + PartialOrd + Clone>(node: &BstNode<T>) -> Option<T> {
+    let mut node = node;
+    while let Some(right) = &node.right {
+        node = right;
+    }
+
+    Some(node.data.clone())
 }
 
-pub fn size<T: PartialEq + PartialOrd>(node: &Box<BstNode<T>>) -> usize {
-    todo!();
+pub fn size<T: PartialEq + PartialOrd>(node: &BstNode<T>) -> usize {
+    let mut size = 0;
+    let mut stack = vec![node];
+    while let Some(node) = stack.pop() {
+        size += 1;
+        stack.extend(node.left.as_deref());
+        stack.extend(node.right.as_deref());
+    }
+
+    size
 }
 
-pub fn height<T: PartialEq + PartialOrd>(node: &Box<BstNode<T>>) -> usize {
-    todo!();
+pub fn height<T: PartialEq + PartialOrd>(node: &BstNode<T>) -> usize {
+    validate::height(Some(node))
 }
 
 #[derive(Clone)]
 pub struct BstNode<T: PartialEq + PartialOrd> {
-    left: Option<Box<BstNode<T>>>,
-    right: Option<Box<BstNode<T>>>,
-    data: T,
+    pub(crate) left: Option<Box<BstNode<T>>>,
+    pub(crate) right: Option<Box<BstNode<T>>>,
+    pub(crate) data: T,
 }
 
 impl<T: PartialEq + PartialOrd> BstNode<T> {
-    fn new(data: T) -> Self {
+    pub(crate) fn new(data: T) -> Self {
         Self {
             data,
             left: None,
@@ -205,9 +340,46 @@ impl<T: PartialEq + PartialOrd> BstNode<T> {
     }
 }
 
+/// Unbalanced trees can be as deep as they are large, so children are torn
+/// down with an explicit stack rather than by recursive drops.
+impl<T: PartialEq + PartialOrd> Drop for BstNode<T> {
+    fn drop(&mut self) {
+        let mut stack: Vec<_> = self
+            .left
+            .take()
+            .into_iter()
+            .chain(self.right.take())
+            .collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+    }
+}
+
+impl<T: PartialEq + PartialOrd> BinaryNode for BstNode<T> {
+    type Value = T;
+
+    fn value(&self) -> &T {
+        &self.data
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod test {
     use super::*;
+    use crate::conformance::{conformance_tests, impl_test_set};
+    use crate::trace::{TraceEvent, TraceRecorder};
+    use crate::validate::ViolationKind;
 
     fn create_empty() -> BinarySearchTree<usize> {
         BinarySearchTree::new(None)
@@ -220,9 +392,14 @@ mod test {
                 left: Some(Box::new(BstNode::<usize>::new(25))),
                 right: Some(Box::new(BstNode::<usize>::new(100))),
             })),
+            len: 3,
+            observer: NoObserver,
         }
     }
 
+    impl_test_set!(BinarySearchTree, |_| BinarySearchTree::new(None), delete);
+    conformance_tests!(BinarySearchTree<usize>);
+
     #[test]
     fn test_insert() {
         let mut bst = create_empty();
@@ -290,6 +467,8 @@ mod test {
                     right: Some(Box::new(Bn8::new(150))),
                 })),
             })),
+            len: 7,
+            observer: NoObserver,
         };
         bst.delete(50);
         assert_eq!(bst.root.unwrap().data, 42);
@@ -393,7 +572,7 @@ mod test {
 
     #[test]
     fn test_size_empty() {
-        let bst = create_filled();
+        let bst = create_empty();
         let size = bst.size();
         assert_eq!(size, 0);
     }
@@ -421,4 +600,268 @@ mod test {
         bst.clear();
         assert_eq!(bst.root.is_none(), true);
     }
+
+    #[test]
+    fn test_validate() {
+        let mut bst = create_filled();
+        bst.insert(17);
+        bst.insert(255);
+        bst.insert(17);
+        let stats = bst.validate().unwrap();
+        assert_eq!(stats, TreeStats { size: 5, height: 3 });
+    }
+
+    #[test]
+    fn test_validate_empty() {
+        let bst = create_empty();
+        assert_eq!(bst.validate().unwrap(), TreeStats::default());
+    }
+
+    #[test]
+    fn test_validate_out_of_order() {
+        // 42 sits in the left subtree of 25 but is larger than it.
+        //      50
+        //    /    \
+        //   25     100
+        //  /
+        // 42
+        let mut bst = create_filled();
+        bst.root.as_mut().unwrap().left.as_mut().unwrap().left =
+            Some(Box::new(BstNode::new(42)));
+        bst.len = 4;
+        let violation = bst.validate().unwrap_err();
+        assert_eq!(violation.path, vec![Direction::Left, Direction::Left]);
+        assert_eq!(violation.kind, ViolationKind::OutOfOrder);
+    }
+
+    #[test]
+    fn test_validate_duplicate() {
+        let mut bst = create_filled();
+        bst.root.as_mut().unwrap().right.as_mut().unwrap().left =
+            Some(Box::new(BstNode::new(50)));
+        bst.len = 4;
+        let violation = bst.validate().unwrap_err();
+        assert_eq!(violation.path, vec![Direction::Right, Direction::Left]);
+        assert_eq!(violation.kind, ViolationKind::Duplicate);
+    }
+
+    #[test]
+    fn test_delete_one_child() {
+        let mut bst = create_filled();
+        bst.insert(17);
+        bst.delete(25);
+        assert_eq!(bst.root.as_ref().unwrap().left.as_ref().unwrap().data, 17);
+        assert_eq!(bst.validate().unwrap(), TreeStats { size: 3, height: 2 });
+    }
+
+    #[test]
+    fn test_delete_two_children() {
+        // Before remove:
+        //        50
+        //    /       \
+        //   25        100
+        //  /  \
+        // 17   42
+        //     /
+        //    30
+        // After remove:
+        //        50
+        //    /       \
+        //   17        100
+        //     \
+        //      42
+        //     /
+        //    30
+        let mut bst = create_filled();
+        for value in [17, 42, 30] {
+            bst.insert(value);
+        }
+        bst.delete(25);
+        let left = bst.root.as_ref().unwrap().left.as_ref().unwrap();
+        assert_eq!(left.data, 17);
+        assert_eq!(left.right.as_ref().unwrap().data, 42);
+        assert_eq!(bst.validate().unwrap(), TreeStats { size: 5, height: 4 });
+    }
+
+    #[test]
+    fn test_delete_missing() {
+        let mut bst = create_filled();
+        bst.delete(75);
+        bst.delete(255);
+        assert_eq!(bst.validate().unwrap(), TreeStats { size: 3, height: 2 });
+    }
+
+    #[test]
+    fn test_degenerate() {
+        // The root's left subtree is a right spine of even values, so every
+        // operation below walks the whole chain.
+        let n = 200_000;
+        let mut preorder = vec![2 * n];
+        preorder.extend((0..n).map(|value| 2 * value));
+        preorder.push(2 * n + 2);
+        let inorder: Vec<u32> = (0..=n + 1).map(|value| 2 * value).collect();
+        let mut bst =
+            BinarySearchTree::from_preorder_inorder(&preorder, &inorder)
+                .unwrap();
+        assert_eq!(bst.height(), n as usize + 1);
+
+        bst.insert(2 * n - 1);
+        assert!(bst.contains(2 * n - 1));
+        bst.delete(2 * n - 4);
+        assert!(!bst.contains(2 * n - 4));
+        // Replacing the root takes the predecessor from the bottom of the
+        // chain.
+        bst.delete(2 * n);
+        assert_eq!(bst.root.as_ref().unwrap().data, 2 * n - 1);
+        assert_eq!(bst.validate().unwrap().size, n as usize + 1);
+    }
+
+    #[test]
+    fn test_observer() {
+        let mut bst = BinarySearchTree::with_observer(TraceRecorder::new());
+        bst.insert(50);
+        bst.insert(25);
+        bst.insert(25);
+        assert_eq!(
+            bst.observer().events(),
+            [
+                TraceEvent::Link { value: 50 },
+                TraceEvent::Compare {
+                    value: 25,
+                    node: 50
+                },
+                TraceEvent::Descend {
+                    direction: Direction::Left,
+                },
+                TraceEvent::Link { value: 25 },
+                TraceEvent::Compare {
+                    value: 25,
+                    node: 50
+                },
+                TraceEvent::Descend {
+                    direction: Direction::Left,
+                },
+                TraceEvent::Compare {
+                    value: 25,
+                    node: 25
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_observer_delete() {
+        let mut bst = BinarySearchTree::with_observer(TraceRecorder::new());
+        for value in [50, 25, 100, 17, 42] {
+            bst.insert(value);
+        }
+        bst.observer_mut().clear();
+        bst.delete(50);
+        assert_eq!(
+            bst.observer().to_json_lines(),
+            [
+                r#"{"event":"compare","value":"50","node":"50"}"#,
+                r#"{"event":"unlink","value":"50"}"#,
+                r#"{"event":"descend","direction":"left"}"#,
+                r#"{"event":"descend","direction":"right"}"#,
+                "",
+            ]
+            .join("\n")
+        );
+        assert_eq!(bst.root.as_ref().unwrap().data, 42);
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let mut bst = create_filled();
+        bst.insert(75);
+        assert_eq!(
+            bst.to_dot(),
+            "digraph BinarySearchTree {
+    n0 [label=\"50\"];
+    n1 [label=\"25\"];
+    n2 [shape=point];
+    n1 -> n2;
+    n3 [shape=point];
+    n1 -> n3;
+    n0 -> n1;
+    n4 [label=\"100\"];
+    n5 [label=\"75\"];
+    n6 [shape=point];
+    n5 -> n6;
+    n7 [shape=point];
+    n5 -> n7;
+    n4 -> n5;
+    n8 [shape=point];
+    n4 -> n8;
+    n0 -> n4;
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_to_dot_empty() {
+        let bst = create_empty();
+        assert_eq!(bst.to_dot(), "digraph BinarySearchTree {\n}\n");
+    }
+
+    #[test]
+    fn test_render_ascii() {
+        let mut bst = create_filled();
+        bst.delete(25);
+        bst.insert(75);
+        bst.insert(150);
+        bst.insert(17);
+        assert_eq!(
+            bst.render_ascii(),
+            [
+                "  50___",
+                " /     \\",
+                "17    100_",
+                "     /    \\",
+                "    75   150",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_render_ascii_null_marker() {
+        let mut bst = create_filled();
+        bst.delete(25);
+        assert_eq!(bst.render_ascii(), " 50_\n/   \\\n*  100\n");
+    }
+
+    #[test]
+    fn test_render_degenerate() {
+        // The shape sorted inserts give, built without their quadratic cost.
+        let values: Vec<u32> = (0..100_000).collect();
+        let bst =
+            BinarySearchTree::from_preorder_inorder(&values, &values).unwrap();
+        let dot = bst.to_dot();
+        // A line per node, per null leaf and per edge, and the braces.
+        assert_eq!(dot.lines().count(), 100_000 + 100_001 + 200_000 + 2);
+        assert!(dot.ends_with("    n2 -> n4;\n    n0 -> n2;\n}\n"));
+
+        // The drawing grows with the square of the depth, so a shorter chain
+        // on a small stack stands in for the long one.
+        let values: Vec<u32> = (0..1000).collect();
+        let bst =
+            BinarySearchTree::from_preorder_inorder(&values, &values).unwrap();
+        let lines = std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn(move || bst.render_ascii().lines().count())
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(lines, 2 * 1000 - 1);
+    }
+
+    #[test]
+    fn test_render_ascii_empty() {
+        let bst = create_empty();
+        assert_eq!(bst.render_ascii(), "");
+    }
 }
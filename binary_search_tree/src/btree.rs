@@ -0,0 +1,1216 @@
+// Copyright (C) 2025 BastIsAwesome (bastisawesomeltd@gmail.com)
+//
+// This file is part of deque.
+//
+// deque is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// deque is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with deque.  If not, see <https://www.gnu.org/licenses/>.
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+
+use crate::validate::{
+    self, Direction, InvariantViolation, TreeStats, ViolationKind,
+};
+
+/// B-tree of minimum degree `B`. Every node but the root holds between
+/// `B - 1` and `2B - 1` keys, and all leaves sit at the same depth.
+pub struct BTree<T: PartialEq + PartialOrd, const B: usize> {
+    root: Option<Box<BTreeNode<T>>>,
+    len: usize,
+}
+
+impl<T: PartialEq + PartialOrd, const B: usize> BTree<T, B> {
+    pub fn new(data: Option<T>) -> Self {
+        const { assert!(B >= 2, "B-trees need a minimum degree of at least 2") };
+
+        let mut tree = Self { root: None, len: 0 };
+        if let Some(data) = data {
+            tree.insert(data);
+        }
+        tree
+    }
+
+    pub fn insert(&mut self, value: T) {
+        let Some(root) = &mut self.root else {
+            self.root = Some(Box::new(BTreeNode::leaf(vec![value])));
+            self.len = 1;
+            return;
+        };
+
+        match insert::<T, B>(root, value) {
+            Insert::Exists => return,
+            Insert::Done => {},
+            Insert::Split(median, right) => {
+                let left = *self.root.take().unwrap();
+                self.root = Some(Box::new(BTreeNode {
+                    keys: vec![median],
+                    children: vec![left, right],
+                }));
+            },
+        }
+        self.len += 1;
+    }
+
+    pub fn delete(&mut self, value: T) {
+        let Some(root) = &mut self.root else {
+            return;
+        };
+
+        if !delete::<T, B>(root, &value) {
+            return;
+        }
+        self.len -= 1;
+
+        // The root may be left without keys after its last two children
+        // merged, in which case the merged child takes its place.
+        if root.keys.is_empty() {
+            self.root = root.children.pop().map(Box::new);
+        }
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let mut node = self.root.as_deref();
+        while let Some(current) = node {
+            let index = lower_bound(&current.keys, &value);
+            if current.keys.get(index) == Some(&value) {
+                return true;
+            }
+            node = current.children.get(index);
+        }
+
+        false
+    }
+
+    pub fn min(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        let mut node = self.root.as_deref()?;
+        while let Some(first) = node.children.first() {
+            node = first;
+        }
+        node.keys.first().cloned()
+    }
+
+    pub fn max(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        let mut node = self.root.as_deref()?;
+        while let Some(last) = node.children.last() {
+            node = last;
+        }
+        node.keys.last().cloned()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn size(&self) -> usize {
+        self.len
+    }
+
+    /// Number of levels of nodes, not of keys.
+    pub fn height(&self) -> usize {
+        let mut height = 0;
+        let mut node = self.root.as_deref();
+        while let Some(current) = node {
+            height += 1;
+            node = current.children.first();
+        }
+        height
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        let mut iter = Iter {
+            stack: vec![],
+            remaining: self.len,
+        };
+        iter.push_first(self.root.as_deref());
+        iter
+    }
+
+    /// Checks that keys are strictly ordered, that every node holds an
+    /// allowed number of keys and children, that all leaves share a depth and
+    /// the stored size.
+    pub fn validate(&self) -> Result<TreeStats, InvariantViolation> {
+        let Some(root) = &self.root else {
+            return Ok(TreeStats::default());
+        };
+
+        let mut check = Check {
+            path: vec![],
+            leaf_depth: None,
+            size: 0,
+        };
+        check.node::<T, B>(root, None, None)?;
+
+        validate::check_size(&[], self.len, check.size)?;
+
+        Ok(TreeStats {
+            size: check.size,
+            height: check.leaf_depth.unwrap() + 1,
+        })
+    }
+}
+
+struct BTreeNode<T> {
+    keys: Vec<T>,
+    /// Empty for leaves, otherwise one more than `keys`.
+    children: Vec<BTreeNode<T>>,
+}
+
+impl<T> BTreeNode<T> {
+    fn leaf(keys: Vec<T>) -> Self {
+        Self {
+            keys,
+            children: vec![],
+        }
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+}
+
+/// Index of the first key not less than `value`.
+fn lower_bound<T: PartialOrd>(keys: &[T], value: &T) -> usize {
+    keys.partition_point(|key| key < value)
+}
+
+enum Insert<T> {
+    Exists,
+    Done,
+    /// The node overflowed and was split. The median moves up to the parent
+    /// with the new right sibling following it.
+    Split(T, BTreeNode<T>),
+}
+
+fn insert<T: PartialOrd, const B: usize>(
+    node: &mut BTreeNode<T>,
+    value: T,
+) -> Insert<T> {
+    let index = lower_bound(&node.keys, &value);
+    if node.keys.get(index) == Some(&value) {
+        return Insert::Exists;
+    }
+
+    if node.is_leaf() {
+        node.keys.insert(index, value);
+    } else {
+        match insert::<T, B>(&mut node.children[index], value) {
+            Insert::Split(median, right) => {
+                node.keys.insert(index, median);
+                node.children.insert(index + 1, right);
+            },
+            done => return done,
+        }
+    }
+
+    if node.keys.len() < 2 * B {
+        return Insert::Done;
+    }
+
+    // 2B keys: B stay, the median moves up and B - 1 move right.
+    let right_keys = node.keys.split_off(B + 1);
+    let median = node.keys.pop().unwrap();
+    let right_children = match node.is_leaf() {
+        true => vec![],
+        false => node.children.split_off(B + 1),
+    };
+    Insert::Split(
+        median,
+        BTreeNode {
+            keys: right_keys,
+            children: right_children,
+        },
+    )
+}
+
+/// Returns whether the value was found and removed. Children left with too
+/// few keys are fixed on the way back up, so only `node` itself may be short
+/// afterwards.
+fn delete<T: PartialOrd, const B: usize>(
+    node: &mut BTreeNode<T>,
+    value: &T,
+) -> bool {
+    let index = lower_bound(&node.keys, value);
+    let found = node.keys.get(index) == Some(value);
+
+    if node.is_leaf() {
+        if found {
+            node.keys.remove(index);
+        }
+        return found;
+    }
+
+    if found {
+        // Replace the key with its predecessor, the largest key on its left.
+        node.keys[index] = take_max::<T, B>(&mut node.children[index]);
+    } else if !delete::<T, B>(&mut node.children[index], value) {
+        return false;
+    }
+
+    fix_child::<T, B>(node, index);
+    true
+}
+
+fn take_max<T: PartialOrd, const B: usize>(node: &mut BTreeNode<T>) -> T {
+    if node.is_leaf() {
+        return node.keys.pop().unwrap();
+    }
+
+    let last = node.children.len() - 1;
+    let max = take_max::<T, B>(&mut node.children[last]);
+    fix_child::<T, B>(node, last);
+    max
+}
+
+/// Tops `node.children[index]` back up to `B - 1` keys by borrowing through
+/// the parent from a sibling that can spare one, or merging with a sibling
+/// that cannot.
+fn fix_child<T, const B: usize>(node: &mut BTreeNode<T>, index: usize) {
+    if node.children[index].keys.len() >= B - 1 {
+        return;
+    }
+
+    if index > 0 && node.children[index - 1].keys.len() > B - 1 {
+        let (before, after) = node.children.split_at_mut(index);
+        let (left, child) = (&mut before[index - 1], &mut after[0]);
+        let separator =
+            mem::replace(&mut node.keys[index - 1], left.keys.pop().unwrap());
+        child.keys.insert(0, separator);
+        if let Some(grandchild) = left.children.pop() {
+            child.children.insert(0, grandchild);
+        }
+        return;
+    }
+
+    if index + 1 < node.children.len()
+        && node.children[index + 1].keys.len() > B - 1
+    {
+        let (before, after) = node.children.split_at_mut(index + 1);
+        let (child, right) = (&mut before[index], &mut after[0]);
+        let separator =
+            mem::replace(&mut node.keys[index], right.keys.remove(0));
+        child.keys.push(separator);
+        if !right.is_leaf() {
+            child.children.push(right.children.remove(0));
+        }
+        return;
+    }
+
+    let left = match index {
+        0 => 0,
+        _ => index - 1,
+    };
+    let right = node.children.remove(left + 1);
+    let separator = node.keys.remove(left);
+    let left = &mut node.children[left];
+    left.keys.push(separator);
+    left.keys.extend(right.keys);
+    left.children.extend(right.children);
+}
+
+/// In-order iterator over a [`BTree`].
+pub struct Iter<'a, T> {
+    /// Nodes being walked, each with the index of its next key.
+    stack: Vec<(&'a BTreeNode<T>, usize)>,
+    remaining: usize,
+}
+
+impl<'a, T> Iter<'a, T> {
+    fn push_first(&mut self, node: Option<&'a BTreeNode<T>>) {
+        let mut node = node;
+        while let Some(current) = node {
+            self.stack.push((current, 0));
+            node = current.children.first();
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (node, index) = self.stack.pop()?;
+            if index == node.keys.len() {
+                continue;
+            }
+
+            self.stack.push((node, index + 1));
+            self.push_first(node.children.get(index + 1));
+            self.remaining -= 1;
+            return Some(&node.keys[index]);
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+/// State carried through a validation walk.
+struct Check {
+    path: Vec<Direction>,
+    leaf_depth: Option<usize>,
+    size: usize,
+}
+
+impl Check {
+    fn fail(&self, kind: ViolationKind) -> Result<(), InvariantViolation> {
+        Err(InvariantViolation::new(self.path.clone(), kind))
+    }
+
+    fn node<T: PartialOrd, const B: usize>(
+        &mut self,
+        node: &BTreeNode<T>,
+        lower: Option<&T>,
+        upper: Option<&T>,
+    ) -> Result<(), InvariantViolation> {
+        self.keys::<T, B>(&node.keys, node.is_leaf(), lower, upper)?;
+
+        if node.is_leaf() {
+            return Ok(());
+        }
+        // Internal keys are values too, unlike the separators of a B+ tree.
+        self.size += node.keys.len();
+
+        for (index, child) in node.children.iter().enumerate() {
+            self.path.push(Direction::Child(index));
+            let lower = match index {
+                0 => lower,
+                _ => node.keys.get(index - 1),
+            };
+            let upper = node.keys.get(index).or(upper);
+            self.node::<T, B>(child, lower, upper)?;
+            self.path.pop();
+        }
+
+        Ok(())
+    }
+
+    /// Checks what B-tree and B+ tree nodes have in common: the number of
+    /// keys and children, the key order and the depth of leaves.
+    fn keys<T: PartialOrd, const B: usize>(
+        &mut self,
+        keys: &[T],
+        is_leaf: bool,
+        lower: Option<&T>,
+        upper: Option<&T>,
+    ) -> Result<(), InvariantViolation> {
+        let min = match self.path.is_empty() {
+            true => 1,
+            false => B - 1,
+        };
+        if keys.len() < min || keys.len() > 2 * B - 1 {
+            return self.fail(ViolationKind::KeyCount {
+                count: keys.len(),
+                min,
+                max: 2 * B - 1,
+            });
+        }
+
+        let mut previous = lower;
+        for key in keys {
+            if previous.is_some_and(|previous| key == previous)
+                || upper.is_some_and(|upper| key == upper)
+            {
+                return self.fail(ViolationKind::Duplicate);
+            }
+            if previous.is_some_and(|previous| key < previous)
+                || upper.is_some_and(|upper| key > upper)
+            {
+                return self.fail(ViolationKind::OutOfOrder);
+            }
+            previous = Some(key);
+        }
+
+        if is_leaf {
+            self.size += keys.len();
+            let depth = self.path.len();
+            let expected = *self.leaf_depth.get_or_insert(depth);
+            if depth != expected {
+                return self.fail(ViolationKind::LeafDepth { depth, expected });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+type NodeId = usize;
+
+/// B+ tree of minimum degree `B`. Values live only in the leaves, which are
+/// linked in order so range scans walk along the bottom level instead of
+/// climbing back up the tree. Internal nodes hold copies of values as
+/// separators, hence the `Clone` bound.
+///
+/// Nodes are kept in an arena and refer to each other by index, which is
+/// what lets leaves point at their successor without shared ownership.
+pub struct BPlusTree<T: PartialEq + PartialOrd + Clone, const B: usize> {
+    nodes: Vec<BPlusNode<T>>,
+    /// Arena slots that can be reused.
+    free: Vec<NodeId>,
+    root: NodeId,
+    len: usize,
+}
+
+enum BPlusNode<T> {
+    /// Every value in `children[i]` is at least `keys[i - 1]` and less than
+    /// `keys[i]`.
+    Internal {
+        keys: Vec<T>,
+        children: Vec<NodeId>,
+    },
+    Leaf {
+        keys: Vec<T>,
+        next: Option<NodeId>,
+    },
+}
+
+impl<T> BPlusNode<T> {
+    fn keys(&self) -> &Vec<T> {
+        match self {
+            BPlusNode::Internal { keys, .. } | BPlusNode::Leaf { keys, .. } => {
+                keys
+            },
+        }
+    }
+
+    fn keys_mut(&mut self) -> &mut Vec<T> {
+        match self {
+            BPlusNode::Internal { keys, .. } | BPlusNode::Leaf { keys, .. } => {
+                keys
+            },
+        }
+    }
+
+    fn children(&self) -> &[NodeId] {
+        match self {
+            BPlusNode::Internal { children, .. } => children,
+            BPlusNode::Leaf { .. } => &[],
+        }
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<NodeId> {
+        match self {
+            BPlusNode::Internal { children, .. } => children,
+            BPlusNode::Leaf { .. } => unreachable!("leaves have no children"),
+        }
+    }
+}
+
+enum PlusInsert<T> {
+    Exists,
+    Done,
+    /// The node overflowed and was split. The separator moves up to the
+    /// parent with the new right sibling following it.
+    Split(T, NodeId),
+}
+
+impl<T: PartialEq + PartialOrd + Clone, const B: usize> BPlusTree<T, B> {
+    pub fn new(data: Option<T>) -> Self {
+        const { assert!(B >= 2, "B+ trees need a minimum degree of at least 2") };
+
+        let mut tree = Self {
+            nodes: vec![BPlusNode::Leaf {
+                keys: vec![],
+                next: None,
+            }],
+            free: vec![],
+            root: 0,
+            len: 0,
+        };
+        if let Some(data) = data {
+            tree.insert(data);
+        }
+        tree
+    }
+
+    pub fn insert(&mut self, value: T) {
+        match self.insert_into(self.root, value) {
+            PlusInsert::Exists => return,
+            PlusInsert::Done => {},
+            PlusInsert::Split(separator, right) => {
+                self.root = self.alloc(BPlusNode::Internal {
+                    keys: vec![separator],
+                    children: vec![self.root, right],
+                });
+            },
+        }
+        self.len += 1;
+    }
+
+    pub fn delete(&mut self, value: T) {
+        if !self.delete_from(self.root, &value) {
+            return;
+        }
+        self.len -= 1;
+
+        if let BPlusNode::Internal { keys, children } =
+            &mut self.nodes[self.root]
+        {
+            if keys.is_empty() {
+                let child = children[0];
+                self.release(self.root);
+                self.root = child;
+            }
+        }
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let leaf = self.find_leaf(&value);
+        self.nodes[leaf].keys().contains(&value)
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.iter().next().cloned()
+    }
+
+    pub fn max(&self) -> Option<T> {
+        let mut node = self.root;
+        while let Some(&last) = self.nodes[node].children().last() {
+            node = last;
+        }
+        self.nodes[node].keys().last().cloned()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn size(&self) -> usize {
+        self.len
+    }
+
+    /// Number of levels of nodes, not of keys.
+    pub fn height(&self) -> usize {
+        if self.is_empty() {
+            return 0;
+        }
+
+        let mut height = 1;
+        let mut node = self.root;
+        while let Some(&first) = self.nodes[node].children().first() {
+            node = first;
+            height += 1;
+        }
+        height
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new(None);
+    }
+
+    pub fn iter(&self) -> Range<'_, T, B> {
+        self.range(..)
+    }
+
+    /// Values within `range`, in order. Finds the first leaf once and then
+    /// follows the links between leaves.
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> Range<'_, T, B> {
+        let (leaf, index) = match range.start_bound() {
+            Bound::Unbounded => (self.first_leaf(), 0),
+            Bound::Included(start) => {
+                let leaf = self.find_leaf(start);
+                (leaf, lower_bound(self.nodes[leaf].keys(), start))
+            },
+            Bound::Excluded(start) => {
+                let leaf = self.find_leaf(start);
+                let keys = self.nodes[leaf].keys();
+                (leaf, keys.partition_point(|key| key <= start))
+            },
+        };
+
+        Range {
+            tree: self,
+            leaf: Some(leaf),
+            index,
+            end: range.end_bound().cloned(),
+        }
+    }
+
+    /// Checks the same node invariants as [`BTree::validate`], with
+    /// separators allowed to equal the first value to their right, and that
+    /// the leaf links visit every leaf in order.
+    pub fn validate(&self) -> Result<TreeStats, InvariantViolation> {
+        if self.is_empty() {
+            return Ok(TreeStats::default());
+        }
+
+        let mut check = Check {
+            path: vec![],
+            leaf_depth: None,
+            size: 0,
+        };
+        let mut leaves = vec![];
+        self.check_node(&mut check, self.root, None, None, &mut leaves)?;
+
+        validate::check_size(&[], self.len, check.size)?;
+
+        let mut linked = vec![];
+        let mut leaf = Some(self.first_leaf());
+        while let Some(current) = leaf {
+            if linked.len() > leaves.len() {
+                break;
+            }
+            linked.push(current);
+            leaf = match &self.nodes[current] {
+                BPlusNode::Leaf { next, .. } => *next,
+                BPlusNode::Internal { .. } => None,
+            };
+        }
+        if linked != leaves {
+            return Err(InvariantViolation::new(
+                vec![],
+                ViolationKind::LeafChain,
+            ));
+        }
+
+        Ok(TreeStats {
+            size: check.size,
+            height: check.leaf_depth.unwrap() + 1,
+        })
+    }
+
+    fn check_node(
+        &self,
+        check: &mut Check,
+        node: NodeId,
+        lower: Option<&T>,
+        upper: Option<&T>,
+        leaves: &mut Vec<NodeId>,
+    ) -> Result<(), InvariantViolation> {
+        let keys = self.nodes[node].keys();
+        let children = self.nodes[node].children();
+        let is_leaf = matches!(self.nodes[node], BPlusNode::Leaf { .. });
+
+        // Leaf values and separators may equal the separator on their left,
+        // so that bound is only checked for order.
+        if lower.is_some_and(|lower| keys.first().is_some_and(|k| k < lower)) {
+            return check.fail(ViolationKind::OutOfOrder);
+        }
+        check.keys::<T, B>(keys, is_leaf, None, upper)?;
+
+        if is_leaf {
+            leaves.push(node);
+            return Ok(());
+        }
+        if children.len() != keys.len() + 1 {
+            return check.fail(ViolationKind::ChildCount {
+                keys: keys.len(),
+                children: children.len(),
+            });
+        }
+
+        for (index, &child) in children.iter().enumerate() {
+            check.path.push(Direction::Child(index));
+            let lower = match index {
+                0 => lower,
+                _ => keys.get(index - 1),
+            };
+            let upper = keys.get(index).or(upper);
+            self.check_node(check, child, lower, upper, leaves)?;
+            check.path.pop();
+        }
+
+        Ok(())
+    }
+
+    fn alloc(&mut self, node: BPlusNode<T>) -> NodeId {
+        match self.free.pop() {
+            Some(id) => {
+                self.nodes[id] = node;
+                id
+            },
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            },
+        }
+    }
+
+    fn release(&mut self, id: NodeId) {
+        self.nodes[id] = BPlusNode::Leaf {
+            keys: vec![],
+            next: None,
+        };
+        self.free.push(id);
+    }
+
+    /// Index of the child of an internal node that `value` belongs under.
+    fn child_index(keys: &[T], value: &T) -> usize {
+        keys.partition_point(|key| key <= value)
+    }
+
+    fn find_leaf(&self, value: &T) -> NodeId {
+        let mut node = self.root;
+        while let BPlusNode::Internal { keys, children } = &self.nodes[node] {
+            node = children[Self::child_index(keys, value)];
+        }
+        node
+    }
+
+    fn first_leaf(&self) -> NodeId {
+        let mut node = self.root;
+        while let Some(&first) = self.nodes[node].children().first() {
+            node = first;
+        }
+        node
+    }
+
+    fn insert_into(&mut self, node: NodeId, value: T) -> PlusInsert<T> {
+        match &mut self.nodes[node] {
+            BPlusNode::Leaf { keys, .. } => {
+                let index = lower_bound(keys, &value);
+                if keys.get(index) == Some(&value) {
+                    return PlusInsert::Exists;
+                }
+                keys.insert(index, value);
+            },
+            BPlusNode::Internal { keys, children } => {
+                let index = Self::child_index(keys, &value);
+                let child = children[index];
+                match self.insert_into(child, value) {
+                    PlusInsert::Split(separator, right) => {
+                        let BPlusNode::Internal { keys, children } =
+                            &mut self.nodes[node]
+                        else {
+                            unreachable!();
+                        };
+                        keys.insert(index, separator);
+                        children.insert(index + 1, right);
+                    },
+                    done => return done,
+                }
+            },
+        }
+
+        if self.nodes[node].keys().len() < 2 * B {
+            return PlusInsert::Done;
+        }
+
+        // Leaves split evenly and copy their first right value up. Internal
+        // nodes split like a B-tree node, moving their median up.
+        let (separator, right) = match &mut self.nodes[node] {
+            BPlusNode::Leaf { keys, next } => {
+                let right_keys = keys.split_off(B);
+                let separator = right_keys[0].clone();
+                let right = BPlusNode::Leaf {
+                    keys: right_keys,
+                    next: next.take(),
+                };
+                (separator, right)
+            },
+            BPlusNode::Internal { keys, children } => {
+                let right_keys = keys.split_off(B + 1);
+                let separator = keys.pop().unwrap();
+                let right = BPlusNode::Internal {
+                    keys: right_keys,
+                    children: children.split_off(B + 1),
+                };
+                (separator, right)
+            },
+        };
+
+        let right = self.alloc(right);
+        if let BPlusNode::Leaf { next, .. } = &mut self.nodes[node] {
+            *next = Some(right);
+        }
+        PlusInsert::Split(separator, right)
+    }
+
+    /// Returns whether the value was found and removed, fixing short
+    /// children on the way back up.
+    fn delete_from(&mut self, node: NodeId, value: &T) -> bool {
+        let (index, child) = match &mut self.nodes[node] {
+            BPlusNode::Leaf { keys, .. } => {
+                let index = lower_bound(keys, value);
+                if keys.get(index) != Some(value) {
+                    return false;
+                }
+                keys.remove(index);
+                return true;
+            },
+            BPlusNode::Internal { keys, children } => {
+                let index = Self::child_index(keys, value);
+                (index, children[index])
+            },
+        };
+
+        if !self.delete_from(child, value) {
+            return false;
+        }
+
+        self.fix_child(node, index);
+        true
+    }
+
+    /// Tops child `index` of `parent` back up to `B - 1` keys by borrowing
+    /// from a sibling that can spare one, or merging with one that cannot.
+    fn fix_child(&mut self, parent: NodeId, index: usize) {
+        let child_at =
+            |tree: &Self, at: usize| tree.nodes[parent].children()[at];
+        let child = child_at(self, index);
+        if self.nodes[child].keys().len() >= B - 1 {
+            return;
+        }
+
+        let is_leaf = matches!(self.nodes[child], BPlusNode::Leaf { .. });
+        let can_spare =
+            |tree: &Self, id: NodeId| tree.nodes[id].keys().len() > B - 1;
+
+        if index > 0 && can_spare(self, child_at(self, index - 1)) {
+            let left = child_at(self, index - 1);
+            let key = self.nodes[left].keys_mut().pop().unwrap();
+            if is_leaf {
+                self.nodes[parent].keys_mut()[index - 1] = key.clone();
+                self.nodes[child].keys_mut().insert(0, key);
+            } else {
+                let separator = mem::replace(
+                    &mut self.nodes[parent].keys_mut()[index - 1],
+                    key,
+                );
+                self.nodes[child].keys_mut().insert(0, separator);
+                let grandchild = self.nodes[left].children_mut().pop().unwrap();
+                self.nodes[child].children_mut().insert(0, grandchild);
+            }
+            return;
+        }
+
+        if index + 1 < self.nodes[parent].children().len()
+            && can_spare(self, child_at(self, index + 1))
+        {
+            let right = child_at(self, index + 1);
+            let key = self.nodes[right].keys_mut().remove(0);
+            if is_leaf {
+                let first = self.nodes[right].keys()[0].clone();
+                self.nodes[parent].keys_mut()[index] = first;
+                self.nodes[child].keys_mut().push(key);
+            } else {
+                let separator = mem::replace(
+                    &mut self.nodes[parent].keys_mut()[index],
+                    key,
+                );
+                self.nodes[child].keys_mut().push(separator);
+                let grandchild = self.nodes[right].children_mut().remove(0);
+                self.nodes[child].children_mut().push(grandchild);
+            }
+            return;
+        }
+
+        // Merge the right node of the pair into the left one, so the first
+        // leaf is never the one released.
+        let left_index = match index {
+            0 => 0,
+            _ => index - 1,
+        };
+        let (left, right) =
+            (child_at(self, left_index), child_at(self, left_index + 1));
+        let separator = self.nodes[parent].keys_mut().remove(left_index);
+        self.nodes[parent].children_mut().remove(left_index + 1);
+
+        let right_node = mem::replace(
+            &mut self.nodes[right],
+            BPlusNode::Leaf {
+                keys: vec![],
+                next: None,
+            },
+        );
+        match (&mut self.nodes[left], right_node) {
+            (
+                BPlusNode::Leaf { keys, next },
+                BPlusNode::Leaf {
+                    keys: right_keys,
+                    next: right_next,
+                },
+            ) => {
+                keys.extend(right_keys);
+                *next = right_next;
+            },
+            (
+                BPlusNode::Internal { keys, children },
+                BPlusNode::Internal {
+                    keys: right_keys,
+                    children: right_children,
+                },
+            ) => {
+                keys.push(separator);
+                keys.extend(right_keys);
+                children.extend(right_children);
+            },
+            _ => unreachable!("siblings are at the same depth"),
+        }
+        self.release(right);
+    }
+}
+
+/// Values of a [`BPlusTree`] in order, walking the linked leaves.
+pub struct Range<'a, T: PartialEq + PartialOrd + Clone, const B: usize> {
+    tree: &'a BPlusTree<T, B>,
+    leaf: Option<NodeId>,
+    index: usize,
+    end: Bound<T>,
+}
+
+impl<'a, T: PartialEq + PartialOrd + Clone, const B: usize> Iterator
+    for Range<'a, T, B>
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let BPlusNode::Leaf { keys, next } = &self.tree.nodes[self.leaf?]
+            else {
+                unreachable!("range iteration only visits leaves");
+            };
+
+            let Some(key) = keys.get(self.index) else {
+                self.leaf = *next;
+                self.index = 0;
+                continue;
+            };
+
+            let in_range = match &self.end {
+                Bound::Included(end) => key <= end,
+                Bound::Excluded(end) => key < end,
+                Bound::Unbounded => true,
+            };
+            if !in_range {
+                self.leaf = None;
+                return None;
+            }
+
+            self.index += 1;
+            return Some(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rng::Rng;
+    use std::collections::BTreeSet;
+
+    type UTree = BTree<usize, 2>;
+    type UPlusTree = BPlusTree<usize, 2>;
+
+    fn create_filled() -> UTree {
+        let mut tree = UTree::new(None);
+        for value in 1..=10 {
+            tree.insert(value * 10);
+        }
+        tree
+    }
+
+    fn create_plus_filled() -> UPlusTree {
+        let mut tree = UPlusTree::new(None);
+        for value in 1..=10 {
+            tree.insert(value * 10);
+        }
+        tree
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut tree = create_filled();
+        tree.insert(50);
+        tree.insert(55);
+        assert_eq!(tree.size(), 11);
+        assert!(tree.contains(55));
+        assert!(!tree.contains(56));
+        assert_eq!(tree.validate().unwrap().size, 11);
+    }
+
+    #[test]
+    fn test_iter() {
+        let tree = create_filled();
+        let values: Vec<_> = tree.iter().copied().collect();
+        assert_eq!(values, [10, 20, 30, 40, 50, 60, 70, 80, 90, 100]);
+        assert_eq!(tree.iter().len(), 10);
+        assert_eq!(UTree::new(None).iter().next(), None);
+    }
+
+    #[test]
+    fn test_delete() {
+        let mut tree = create_filled();
+        for value in [40, 10, 100, 70, 255] {
+            tree.delete(value);
+            assert!(tree.validate().is_ok());
+        }
+        let values: Vec<_> = tree.iter().copied().collect();
+        assert_eq!(values, [20, 30, 50, 60, 80, 90]);
+
+        for value in [20, 30, 50, 60, 80, 90] {
+            tree.delete(value);
+        }
+        assert!(tree.is_empty());
+        assert_eq!(tree.height(), 0);
+    }
+
+    #[test]
+    fn test_min_max_height() {
+        let mut tree = BTree::<usize, 3>::new(Some(1));
+        for value in 2..=1000 {
+            tree.insert(value);
+        }
+        assert_eq!(tree.min(), Some(1));
+        assert_eq!(tree.max(), Some(1000));
+        // Every node holds at least two keys, so a thousand keys need no
+        // more than log3(1000) + 1 levels.
+        assert!(tree.height() <= 7);
+        assert!(tree.validate().is_ok());
+
+        tree.clear();
+        assert_eq!(tree.min(), None);
+    }
+
+    #[test]
+    fn test_validate_key_count() {
+        let tree = BTree::<usize, 2> {
+            root: Some(Box::new(BTreeNode {
+                keys: vec![50],
+                children: vec![
+                    BTreeNode::leaf(vec![]),
+                    BTreeNode::leaf(vec![60]),
+                ],
+            })),
+            len: 2,
+        };
+        let violation = tree.validate().unwrap_err();
+        assert_eq!(violation.path, vec![Direction::Child(0)]);
+        assert_eq!(
+            violation.kind,
+            ViolationKind::KeyCount {
+                count: 0,
+                min: 1,
+                max: 3,
+            }
+        );
+        assert_eq!(
+            violation.to_string(),
+            "root.children[0]: holds 0 keys but must hold between 1 and 3"
+        );
+    }
+
+    #[test]
+    fn test_matches_btree_set() {
+        for seed in 1..=32 {
+            let mut rng = Rng::new(seed);
+            let mut tree = UTree::new(None);
+            let mut set = BTreeSet::new();
+
+            for _ in 0..600 {
+                let value = (rng.next_u64() % 128) as usize;
+                if rng.next_u64().is_multiple_of(3) {
+                    tree.delete(value);
+                    set.remove(&value);
+                } else {
+                    tree.insert(value);
+                    set.insert(value);
+                }
+
+                assert!(tree.validate().is_ok(), "seed {seed}");
+                assert_eq!(tree.size(), set.len());
+            }
+
+            assert!(tree.iter().eq(set.iter()), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn test_plus_insert() {
+        let mut tree = create_plus_filled();
+        tree.insert(50);
+        tree.insert(55);
+        assert_eq!(tree.size(), 11);
+        assert!(tree.contains(55));
+        assert!(!tree.contains(56));
+        assert_eq!(tree.validate().unwrap().size, 11);
+    }
+
+    #[test]
+    fn test_plus_range() {
+        let tree = create_plus_filled();
+        let values: Vec<_> = tree.range(25..=70).copied().collect();
+        assert_eq!(values, [30, 40, 50, 60, 70]);
+
+        let values: Vec<_> = tree.range(30..70).copied().collect();
+        assert_eq!(values, [30, 40, 50, 60]);
+
+        let values: Vec<_> = tree
+            .range((Bound::Excluded(90), Bound::Unbounded))
+            .collect();
+        assert_eq!(values, [&100]);
+
+        assert_eq!(tree.range(101..).next(), None);
+        assert_eq!(tree.iter().count(), 10);
+    }
+
+    #[test]
+    fn test_plus_delete() {
+        let mut tree = create_plus_filled();
+        for value in [40, 10, 100, 70, 255] {
+            tree.delete(value);
+            assert!(tree.validate().is_ok());
+        }
+        let values: Vec<_> = tree.iter().copied().collect();
+        assert_eq!(values, [20, 30, 50, 60, 80, 90]);
+        assert_eq!(tree.min(), Some(20));
+        assert_eq!(tree.max(), Some(90));
+
+        for value in [20, 30, 50, 60, 80, 90] {
+            tree.delete(value);
+        }
+        assert!(tree.is_empty());
+        assert_eq!(tree.height(), 0);
+        assert_eq!(tree.max(), None);
+    }
+
+    #[test]
+    fn test_plus_matches_btree_set() {
+        for seed in 1..=32 {
+            let mut rng = Rng::new(seed);
+            let mut tree = UPlusTree::new(None);
+            let mut set = BTreeSet::new();
+
+            for _ in 0..600 {
+                let value = (rng.next_u64() % 128) as usize;
+                if rng.next_u64().is_multiple_of(3) {
+                    tree.delete(value);
+                    set.remove(&value);
+                } else {
+                    tree.insert(value);
+                    set.insert(value);
+                }
+
+                assert!(tree.validate().is_ok(), "seed {seed}");
+                assert_eq!(tree.size(), set.len());
+                assert_eq!(tree.contains(value), set.contains(&value));
+            }
+
+            assert!(tree.iter().eq(set.iter()), "seed {seed}");
+            assert!(tree.range(20..60).eq(set.range(20..60)), "seed {seed}");
+        }
+    }
+}
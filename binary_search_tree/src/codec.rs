@@ -0,0 +1,596 @@
+// Copyright (C) 2025 BastIsAwesome (bastisawesomeltd@gmail.com)
+//
+// This file is part of deque.
+//
+// deque is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// deque is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with deque.  If not, see <https://www.gnu.org/licenses/>.
+use std::fmt;
+
+use crate::bst::BstNode;
+use crate::validate::InvariantViolation;
+
+type Link<T> = Option<Box<BstNode<T>>>;
+
+/// First bytes of every encoded tree.
+pub const MAGIC: [u8; 4] = *b"BSTR";
+/// Format version written by [`BinarySearchTree::encode`], and the only one
+/// [`BinarySearchTree::decode`] reads.
+///
+/// [`BinarySearchTree::encode`]: crate::bst::BinarySearchTree::encode
+/// [`BinarySearchTree::decode`]: crate::bst::BinarySearchTree::decode
+pub const VERSION: u8 = 1;
+
+/// Marks a missing child in the pre-order stream.
+const NULL: u8 = 0;
+/// Marks a node, whose encoded value follows.
+const NODE: u8 = 1;
+
+/// Magic, version and node count.
+const HEADER_LEN: usize = MAGIC.len() + 1 + 8;
+const CHECKSUM_LEN: usize = 4;
+
+/// Turns values into bytes and back.
+///
+/// A codec is a separate value rather than a trait on `T` so one type can be
+/// written in more than one way.
+pub trait Codec<T> {
+    fn encode(&self, value: &T, out: &mut Vec<u8>);
+
+    /// Reads one value from the front of `input`, advancing past it.
+    fn decode(&self, input: &mut &[u8]) -> Result<T, DecodeError>;
+}
+
+/// Writes the primitive integers as fixed-width little-endian bytes.
+/// `usize` is always written as eight bytes, so files move between
+/// platforms.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LittleEndian;
+
+macro_rules! little_endian {
+    ($($int:ty),*) => {
+        $(
+            impl Codec<$int> for LittleEndian {
+                fn encode(&self, value: &$int, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&value.to_le_bytes());
+                }
+
+                fn decode(
+                    &self,
+                    input: &mut &[u8],
+                ) -> Result<$int, DecodeError> {
+                    let bytes = read_bytes(input, size_of::<$int>())?;
+                    Ok(<$int>::from_le_bytes(bytes.try_into().unwrap()))
+                }
+            }
+        )*
+    };
+}
+
+little_endian!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl Codec<usize> for LittleEndian {
+    fn encode(&self, value: &usize, out: &mut Vec<u8>) {
+        self.encode(&(*value as u64), out);
+    }
+
+    fn decode(&self, input: &mut &[u8]) -> Result<usize, DecodeError> {
+        let value: u64 = self.decode(input)?;
+        usize::try_from(value).map_err(|_| DecodeError::InvalidValue)
+    }
+}
+
+/// Writes strings as a little-endian `u32` byte length followed by UTF-8.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Utf8;
+
+impl Codec<String> for Utf8 {
+    fn encode(&self, value: &String, out: &mut Vec<u8>) {
+        let len = u32::try_from(value.len()).expect("string too long");
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(value.as_bytes());
+    }
+
+    fn decode(&self, input: &mut &[u8]) -> Result<String, DecodeError> {
+        let len: u32 = LittleEndian.decode(input)?;
+        let bytes = read_bytes(input, len as usize)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| DecodeError::InvalidValue)
+    }
+}
+
+/// Splits `len` bytes off the front of `input`, for use in [`Codec`]
+/// implementations.
+pub fn read_bytes<'a>(
+    input: &mut &'a [u8],
+    len: usize,
+) -> Result<&'a [u8], DecodeError> {
+    if input.len() < len {
+        return Err(DecodeError::Truncated);
+    }
+
+    let (bytes, rest) = input.split_at(len);
+    *input = rest;
+    Ok(bytes)
+}
+
+/// Why bytes could not be turned back into a tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input does not start with [`MAGIC`].
+    BadMagic,
+    /// The input was written by a format version this build cannot read.
+    UnsupportedVersion(u8),
+    /// The input ends in the middle of the tree.
+    Truncated,
+    /// The checksum stored at the end does not match the bytes before it.
+    ChecksumMismatch { stored: u32, actual: u32 },
+    /// A byte where a node or null marker was expected is neither.
+    BadMarker(u8),
+    /// The tree ends before the body does.
+    TrailingBytes,
+    /// The header's node count does not match the nodes in the body.
+    CountMismatch { stored: u64, actual: u64 },
+    /// The codec rejected a value's bytes.
+    InvalidValue,
+    /// The traversals given to `from_preorder_inorder` do not describe the
+    /// same tree.
+    TraversalMismatch,
+    /// The bytes describe a tree that breaks the search tree order.
+    Invalid(InvariantViolation),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::BadMagic => write!(f, "input is not an encoded tree"),
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "format version {version} is not supported")
+            },
+            DecodeError::Truncated => write!(f, "input ends too early"),
+            DecodeError::ChecksumMismatch { stored, actual } => write!(
+                f,
+                "checksum is {actual:#010x} but {stored:#010x} was stored"
+            ),
+            DecodeError::BadMarker(marker) => {
+                write!(f, "{marker:#04x} is not a node or null marker")
+            },
+            DecodeError::TrailingBytes => {
+                write!(f, "input continues after the tree ends")
+            },
+            DecodeError::CountMismatch { stored, actual } => write!(
+                f,
+                "header promises {stored} nodes but {actual} were found"
+            ),
+            DecodeError::InvalidValue => write!(f, "value could not be read"),
+            DecodeError::TraversalMismatch => {
+                write!(f, "traversals do not describe the same tree")
+            },
+            DecodeError::Invalid(violation) => {
+                write!(f, "decoded tree is invalid: {violation}")
+            },
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// 32-bit FNV-1a. Catches accidental corruption, not tampering.
+fn checksum(bytes: &[u8]) -> u32 {
+    let mut hash: u32 = 0x811c_9dc5;
+    for &byte in bytes {
+        hash ^= u32::from(byte);
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash
+}
+
+/// Writes the header, the nodes in pre-order with a marker before each node
+/// and for each missing child, and a checksum of everything before it.
+pub(crate) fn encode<T, C>(
+    root: Option<&BstNode<T>>,
+    len: usize,
+    codec: &C,
+) -> Vec<u8>
+where
+    T: PartialEq + PartialOrd,
+    C: Codec<T>,
+{
+    let mut out = Vec::with_capacity(HEADER_LEN + 2 * len + CHECKSUM_LEN);
+    out.extend_from_slice(&MAGIC);
+    out.push(VERSION);
+    out.extend_from_slice(&(len as u64).to_le_bytes());
+
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        let Some(node) = node else {
+            out.push(NULL);
+            continue;
+        };
+
+        out.push(NODE);
+        codec.encode(&node.data, &mut out);
+        stack.push(node.right.as_deref());
+        stack.push(node.left.as_deref());
+    }
+
+    let sum = checksum(&out);
+    out.extend_from_slice(&sum.to_le_bytes());
+    out
+}
+
+/// Reads what [`encode`] wrote, returning the root and the number of nodes.
+/// The shape is rebuilt exactly, but the order of the values is not checked.
+pub(crate) fn decode<T, C>(
+    bytes: &[u8],
+    codec: &C,
+) -> Result<(Link<T>, usize), DecodeError>
+where
+    T: PartialEq + PartialOrd,
+    C: Codec<T>,
+{
+    if bytes.len() < HEADER_LEN + CHECKSUM_LEN {
+        return Err(DecodeError::Truncated);
+    }
+    if bytes[..MAGIC.len()] != MAGIC {
+        return Err(DecodeError::BadMagic);
+    }
+    let version = bytes[MAGIC.len()];
+    if version != VERSION {
+        return Err(DecodeError::UnsupportedVersion(version));
+    }
+
+    let (content, stored) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+    let stored = u32::from_le_bytes(stored.try_into().unwrap());
+    let actual = checksum(content);
+    if stored != actual {
+        return Err(DecodeError::ChecksumMismatch { stored, actual });
+    }
+
+    let mut input = &content[MAGIC.len() + 1..];
+    let count: u64 = LittleEndian.decode(&mut input)?;
+
+    let mut shape = Shape::default();
+    // Nodes still waiting for a child, each with whether its left child has
+    // been read yet.
+    let mut pending: Vec<(usize, bool)> = vec![];
+    let mut root = None;
+    loop {
+        let marker = *read_bytes(&mut input, 1)?.first().unwrap();
+        let node = match marker {
+            NULL => None,
+            NODE => Some(shape.push(codec.decode(&mut input)?)),
+            marker => return Err(DecodeError::BadMarker(marker)),
+        };
+
+        match pending.last_mut() {
+            None => root = node,
+            Some((parent, left_done)) => {
+                let parent = *parent;
+                match left_done {
+                    false => {
+                        shape.left[parent] = node;
+                        *left_done = true;
+                    },
+                    true => {
+                        shape.right[parent] = node;
+                        pending.pop();
+                    },
+                }
+            },
+        }
+        if let Some(node) = node {
+            pending.push((node, false));
+        }
+
+        if pending.is_empty() {
+            break;
+        }
+    }
+
+    if !input.is_empty() {
+        return Err(DecodeError::TrailingBytes);
+    }
+    let actual = shape.values.len() as u64;
+    if actual != count {
+        return Err(DecodeError::CountMismatch {
+            stored: count,
+            actual,
+        });
+    }
+
+    let len = shape.values.len();
+    Ok((shape.build(root), len))
+}
+
+/// Rebuilds a tree from its pre-order and in-order traversals, which must
+/// hold the same distinct values.
+pub(crate) fn from_preorder_inorder<T>(
+    preorder: &[T],
+    inorder: &[T],
+) -> Result<Link<T>, DecodeError>
+where
+    T: PartialEq + PartialOrd + Clone,
+{
+    if preorder.len() != inorder.len() {
+        return Err(DecodeError::TraversalMismatch);
+    }
+
+    // Each pre-order value is the left child of the node before it, unless
+    // that node's in-order turn has come, in which case it is the right
+    // child of the last node whose turn has passed.
+    let mut shape = Shape::default();
+    let mut stack: Vec<usize> = vec![];
+    let mut next_inorder = 0;
+    for value in preorder {
+        let node = shape.push(value.clone());
+        let mut parent = None;
+        while let Some(&top) = stack.last() {
+            if inorder.get(next_inorder) != Some(&shape.values[top]) {
+                break;
+            }
+            parent = stack.pop();
+            next_inorder += 1;
+        }
+
+        match (parent, stack.last()) {
+            (Some(parent), _) => shape.right[parent] = Some(node),
+            (None, Some(&parent)) => shape.left[parent] = Some(node),
+            (None, None) => {},
+        }
+        stack.push(node);
+    }
+
+    // Whatever is left on the stack must come out in in-order order.
+    while let Some(top) = stack.pop() {
+        if inorder.get(next_inorder) != Some(&shape.values[top]) {
+            return Err(DecodeError::TraversalMismatch);
+        }
+        next_inorder += 1;
+    }
+    if next_inorder != inorder.len() {
+        return Err(DecodeError::TraversalMismatch);
+    }
+
+    let root = (!preorder.is_empty()).then_some(0);
+    Ok(shape.build(root))
+}
+
+/// A tree described by node indices, where every child comes after its
+/// parent.
+struct Shape<T> {
+    values: Vec<T>,
+    left: Vec<Option<usize>>,
+    right: Vec<Option<usize>>,
+}
+
+impl<T> Default for Shape<T> {
+    fn default() -> Self {
+        Self {
+            values: vec![],
+            left: vec![],
+            right: vec![],
+        }
+    }
+}
+
+impl<T: PartialEq + PartialOrd> Shape<T> {
+    fn push(&mut self, value: T) -> usize {
+        self.values.push(value);
+        self.left.push(None);
+        self.right.push(None);
+        self.values.len() - 1
+    }
+
+    /// Builds the nodes from the last to the first, so both children of a
+    /// node are always ready before it. Avoids recursing, since the tree
+    /// may be as deep as it is large.
+    fn build(self, root: Option<usize>) -> Link<T> {
+        let mut nodes: Vec<Link<T>> = Vec::with_capacity(self.values.len());
+        nodes.resize_with(self.values.len(), || None);
+        let children = self.left.into_iter().zip(self.right);
+        for (index, (value, (left, right))) in
+            self.values.into_iter().zip(children).enumerate().rev()
+        {
+            let mut node = Box::new(BstNode::new(value));
+            node.left = left.and_then(|left| nodes[left].take());
+            node.right = right.and_then(|right| nodes[right].take());
+            nodes[index] = Some(node);
+        }
+
+        root.and_then(|root| nodes[root].take())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bst::BinarySearchTree;
+    use crate::validate::{Direction, ViolationKind};
+
+    fn create_filled() -> BinarySearchTree<usize> {
+        let mut bst = BinarySearchTree::new(None);
+        for value in [50, 25, 100, 17, 42, 150] {
+            bst.insert(value);
+        }
+        bst
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let bst = create_filled();
+        let bytes = bst.encode(&LittleEndian);
+        assert_eq!(bytes[..5], *b"BSTR\x01");
+
+        let decoded =
+            BinarySearchTree::<usize>::decode(&bytes, &LittleEndian).unwrap();
+        assert_eq!(decoded.render_ascii(), bst.render_ascii());
+        assert_eq!(decoded.size(), 6);
+        assert!(decoded.validate().is_ok());
+    }
+
+    #[test]
+    fn test_round_trip_empty() {
+        let bst = BinarySearchTree::<u32>::new(None);
+        let bytes = bst.encode(&LittleEndian);
+        let decoded =
+            BinarySearchTree::<u32>::decode(&bytes, &LittleEndian).unwrap();
+        assert!(decoded.is_empty());
+    }
+
+    #[test]
+    fn test_round_trip_strings() {
+        let mut bst = BinarySearchTree::new(None);
+        for value in ["m", "c", "x", "día"] {
+            bst.insert(value.to_string());
+        }
+        let bytes = bst.encode(&Utf8);
+        let decoded =
+            BinarySearchTree::<String>::decode(&bytes, &Utf8).unwrap();
+        assert_eq!(decoded.to_dot(), bst.to_dot());
+    }
+
+    #[test]
+    fn test_round_trip_deep() {
+        // A chain this long would overflow the stack if either direction
+        // recursed.
+        let values: Vec<u32> = (0..200_000).collect();
+        let bst =
+            BinarySearchTree::from_preorder_inorder(&values, &values).unwrap();
+        assert_eq!(bst.height(), 200_000);
+
+        let bytes = bst.encode(&LittleEndian);
+        let decoded =
+            BinarySearchTree::<u32>::decode(&bytes, &LittleEndian).unwrap();
+        assert_eq!(decoded.encode(&LittleEndian), bytes);
+    }
+
+    #[test]
+    fn test_checksum_mismatch() {
+        let mut bytes = create_filled().encode(&LittleEndian);
+        bytes[20] ^= 0xff;
+        let error = BinarySearchTree::<usize>::decode(&bytes, &LittleEndian)
+            .err()
+            .unwrap();
+        assert!(matches!(error, DecodeError::ChecksumMismatch { .. }));
+    }
+
+    #[test]
+    fn test_header_errors() {
+        let bytes = create_filled().encode(&LittleEndian);
+        let decode = |bytes: &[u8]| {
+            BinarySearchTree::<usize>::decode(bytes, &LittleEndian)
+                .err()
+                .unwrap()
+        };
+
+        assert_eq!(decode(&bytes[..10]), DecodeError::Truncated);
+
+        let mut wrong = bytes.clone();
+        wrong[0] = b'X';
+        assert_eq!(decode(&wrong), DecodeError::BadMagic);
+
+        let mut wrong = bytes.clone();
+        wrong[4] = 2;
+        assert_eq!(decode(&wrong), DecodeError::UnsupportedVersion(2));
+        assert_eq!(
+            DecodeError::UnsupportedVersion(2).to_string(),
+            "format version 2 is not supported"
+        );
+    }
+
+    /// Re-signs a hand-edited body so it gets past the checksum.
+    fn sign(mut bytes: Vec<u8>) -> Vec<u8> {
+        bytes.truncate(bytes.len() - CHECKSUM_LEN);
+        let sum = checksum(&bytes);
+        bytes.extend_from_slice(&sum.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_body_errors() {
+        let decode = |bytes: &[u8]| {
+            BinarySearchTree::<u8>::decode(&sign(bytes.to_vec()), &LittleEndian)
+                .err()
+                .unwrap()
+        };
+        let header = |count: u64| {
+            let mut bytes = b"BSTR\x01".to_vec();
+            bytes.extend_from_slice(&count.to_le_bytes());
+            bytes
+        };
+
+        // One node, then the body ends before its right child.
+        let mut bytes = header(1);
+        bytes.extend_from_slice(&[NODE, 7, NULL, 0, 0, 0, 0]);
+        assert_eq!(decode(&bytes), DecodeError::Truncated);
+
+        let mut bytes = header(1);
+        bytes.extend_from_slice(&[NODE, 7, 9, NULL, 0, 0, 0, 0]);
+        assert_eq!(decode(&bytes), DecodeError::BadMarker(9));
+
+        let mut bytes = header(1);
+        bytes.extend_from_slice(&[NODE, 7, NULL, NULL, NULL, 0, 0, 0, 0]);
+        assert_eq!(decode(&bytes), DecodeError::TrailingBytes);
+
+        let mut bytes = header(2);
+        bytes.extend_from_slice(&[NODE, 7, NULL, NULL, 0, 0, 0, 0]);
+        assert_eq!(
+            decode(&bytes),
+            DecodeError::CountMismatch {
+                stored: 2,
+                actual: 1
+            }
+        );
+
+        // 9 as the left child of 7.
+        let mut bytes = header(2);
+        bytes.extend_from_slice(&[NODE, 7, NODE, 9, NULL, NULL, NULL]);
+        bytes.extend_from_slice(&[0, 0, 0, 0]);
+        let DecodeError::Invalid(violation) = decode(&bytes) else {
+            panic!("out of order values were accepted");
+        };
+        assert_eq!(violation.path, vec![Direction::Left]);
+        assert_eq!(violation.kind, ViolationKind::OutOfOrder);
+    }
+
+    #[test]
+    fn test_from_preorder_inorder() {
+        let bst = BinarySearchTree::from_preorder_inorder(
+            &[50, 25, 17, 42, 100, 150],
+            &[17, 25, 42, 50, 100, 150],
+        )
+        .unwrap();
+        assert_eq!(bst.render_ascii(), create_filled().render_ascii());
+        assert_eq!(bst.size(), 6);
+        assert!(bst.validate().is_ok());
+
+        let empty = BinarySearchTree::<u8>::from_preorder_inorder(&[], &[]);
+        assert!(empty.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_from_preorder_inorder_mismatch() {
+        let error = |preorder: &[u8], inorder: &[u8]| {
+            BinarySearchTree::from_preorder_inorder(preorder, inorder)
+                .err()
+                .unwrap()
+        };
+
+        assert_eq!(error(&[2, 1], &[1]), DecodeError::TraversalMismatch);
+        assert_eq!(
+            error(&[2, 1, 3], &[1, 3, 4]),
+            DecodeError::TraversalMismatch
+        );
+        // A valid shape, but the values break the search tree order.
+        assert!(matches!(error(&[1, 2], &[2, 1]), DecodeError::Invalid(_)));
+    }
+}
@@ -0,0 +1,595 @@
+// Copyright (C) 2025 BastIsAwesome (bastisawesomeltd@gmail.com)
+//
+// This file is part of deque.
+//
+// deque is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// deque is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with deque.  If not, see <https://www.gnu.org/licenses/>.
+use std::ops::{Bound, RangeBounds};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::thread;
+
+use crate::rng::AtomicRng;
+use crate::skip_list::{level_from_bits, MAX_LEVEL};
+
+type Link<T> = Option<Arc<Node<T>>>;
+
+/// Ordered set that many threads can read and write at once, built as the
+/// lazy skip list of Herlihy, Lev, Luchangco and Shavit.
+///
+/// Writers lock only the few nodes around the value they change, so threads
+/// working on different parts of the set do not wait for each other.
+/// Removal first marks a node as deleted and only then unlinks it, and a new
+/// node only counts as present once it is linked on every level. Searches
+/// take no node locks at all; each link sits behind its own read-write lock
+/// that is held just long enough to follow it.
+///
+/// Nodes are reference counted, so a thread still walking through a node
+/// that another thread removed keeps it alive until it moves on.
+pub struct ConcurrentSkipList<T: PartialEq + PartialOrd> {
+    head: Arc<Node<T>>,
+    /// Drawn from without a lock, so inserts never wait on each other here.
+    levels: AtomicRng,
+    len: AtomicUsize,
+}
+
+struct Node<T> {
+    /// `None` only for the head.
+    value: Option<T>,
+    /// One link per level the node takes part in, lowest first.
+    next: Vec<RwLock<Link<T>>>,
+    /// Held while the links leaving this node are changed.
+    lock: Mutex<()>,
+    /// Set once the node has been logically removed.
+    marked: AtomicBool,
+    /// Set once the node is linked on every one of its levels.
+    fully_linked: AtomicBool,
+}
+
+impl<T> Node<T> {
+    fn new(value: Option<T>, levels: usize) -> Self {
+        Self {
+            value,
+            next: (0..levels).map(|_| RwLock::new(None)).collect(),
+            lock: Mutex::new(()),
+            marked: AtomicBool::new(false),
+            fully_linked: AtomicBool::new(false),
+        }
+    }
+
+    fn value(&self) -> &T {
+        self.value.as_ref().expect("head has no value")
+    }
+
+    fn next(&self, level: usize) -> Link<T> {
+        self.next[level].read().unwrap().clone()
+    }
+
+    fn is_marked(&self) -> bool {
+        self.marked.load(Ordering::Acquire)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ()> {
+        self.lock.lock().unwrap()
+    }
+}
+
+/// The nodes around a value on every level, found by one search.
+struct Window<T> {
+    preds: Vec<Arc<Node<T>>>,
+    succs: Vec<Link<T>>,
+    /// Highest level on which the value itself was found.
+    found: Option<usize>,
+}
+
+impl<T: PartialEq + PartialOrd> ConcurrentSkipList<T> {
+    /// Seeds the level generator from per-process randomness.
+    pub fn new() -> Self {
+        Self::with_levels(AtomicRng::from_entropy())
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_levels(AtomicRng::new(seed))
+    }
+
+    fn with_levels(levels: AtomicRng) -> Self {
+        let head = Node::new(None, MAX_LEVEL);
+        head.fully_linked.store(true, Ordering::Release);
+        Self {
+            head: Arc::new(head),
+            levels,
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns whether the value was added.
+    pub fn insert(&self, value: T) -> bool {
+        let levels = level_from_bits(self.levels.next_u64());
+        loop {
+            let window = self.find(&value);
+            if let Some(level) = window.found {
+                let found = window.succs[level].as_ref().unwrap();
+                if !found.is_marked() {
+                    // Present, or about to be; wait until it fully is so
+                    // the answer never runs ahead of `contains`.
+                    while !found.fully_linked.load(Ordering::Acquire) {
+                        thread::yield_now();
+                    }
+                    return false;
+                }
+                // Being removed. Give the remover a chance to unlink it
+                // before trying again.
+                thread::yield_now();
+                continue;
+            }
+
+            let Some(_guards) = lock_window(&window, levels, |succ| {
+                succ.as_ref().is_none_or(|succ| !succ.is_marked())
+            }) else {
+                continue;
+            };
+
+            let node = Arc::new(Node::new(Some(value), levels));
+            for (level, succ) in window.succs.iter().take(levels).enumerate() {
+                *node.next[level].write().unwrap() = succ.clone();
+            }
+            for (level, pred) in window.preds.iter().take(levels).enumerate() {
+                *pred.next[level].write().unwrap() = Some(node.clone());
+            }
+            node.fully_linked.store(true, Ordering::Release);
+            self.len.fetch_add(1, Ordering::Relaxed);
+            return true;
+        }
+    }
+
+    /// Returns whether the value was found and removed.
+    pub fn remove(&self, value: T) -> bool {
+        let mut marked: Option<Arc<Node<T>>> = None;
+        loop {
+            let window = self.find(&value);
+            let victim = match &marked {
+                // Marked on an earlier pass; only the unlinking is left.
+                Some(victim) => victim.clone(),
+                None => {
+                    let Some(level) = window.found else {
+                        return false;
+                    };
+                    let found = window.succs[level].clone().unwrap();
+                    // Only a fully linked node found on its own top level is
+                    // safe to take apart.
+                    if !found.fully_linked.load(Ordering::Acquire)
+                        || found.next.len() != level + 1
+                        || found.is_marked()
+                    {
+                        return false;
+                    }
+
+                    {
+                        let _guard = found.lock();
+                        if found.is_marked() {
+                            // Another thread got there first.
+                            return false;
+                        }
+                        found.marked.store(true, Ordering::Release);
+                    }
+                    marked = Some(found.clone());
+                    found
+                },
+            };
+
+            let levels = victim.next.len();
+            let Some(_guards) = lock_window(&window, levels, |succ| {
+                succ.as_ref().is_some_and(|succ| Arc::ptr_eq(succ, &victim))
+            }) else {
+                continue;
+            };
+
+            for level in (0..levels).rev() {
+                *window.preds[level].next[level].write().unwrap() =
+                    victim.next(level);
+            }
+            self.len.fetch_sub(1, Ordering::Relaxed);
+            return true;
+        }
+    }
+
+    /// Wait-free apart from the short read locks on each link followed.
+    pub fn contains(&self, value: T) -> bool {
+        self.seek(&value)
+            .filter(|node| node.value() == &value)
+            .is_some_and(|node| {
+                node.fully_linked.load(Ordering::Acquire) && !node.is_marked()
+            })
+    }
+
+    /// Number of values at some recent moment. Only exact while no other
+    /// thread is writing.
+    pub fn size(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size() == 0
+    }
+
+    pub fn iter(&self) -> Range<'_, T>
+    where
+        T: Clone,
+    {
+        self.range(..)
+    }
+
+    /// Values within `range`, in order.
+    ///
+    /// The iterator is weakly consistent: it never yields a value twice or
+    /// out of order and it sees every value that stays in the set for the
+    /// whole walk, but values added or removed while it runs may or may not
+    /// show up. Values are cloned out, so no lock is held between calls.
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> Range<'_, T>
+    where
+        T: Clone,
+    {
+        let next = match range.start_bound() {
+            Bound::Unbounded => self.head.next(0),
+            Bound::Included(start) => self.seek(start),
+            Bound::Excluded(start) => {
+                let mut next = self.seek(start);
+                while let Some(node) =
+                    next.as_ref().filter(|node| node.value() <= start)
+                {
+                    next = node.next(0);
+                }
+                next
+            },
+        };
+
+        Range {
+            _list: self,
+            next,
+            end: range.end_bound().cloned(),
+        }
+    }
+
+    /// The first node on the lowest level that is not before `value`.
+    ///
+    /// Unlike [`find`](Self::find) this remembers nothing about the levels
+    /// it passes through, so the read paths neither allocate nor clone the
+    /// head.
+    fn seek(&self, value: &T) -> Link<T> {
+        // The last node passed so far; the head is only borrowed.
+        let mut pred: Link<T> = None;
+        let mut curr = None;
+        for level in (0..MAX_LEVEL).rev() {
+            curr = pred.as_deref().unwrap_or(&self.head).next(level);
+            while let Some(node) = curr.take_if(|node| node.value() < value) {
+                curr = node.next(level);
+                pred = Some(node);
+            }
+        }
+        curr
+    }
+
+    /// Finds, on every level, the last node before `value` and the node
+    /// after it, which is `value`'s own node if it is there. Only the write
+    /// paths need all of this.
+    fn find(&self, value: &T) -> Window<T> {
+        let mut window = Window {
+            preds: vec![self.head.clone(); MAX_LEVEL],
+            succs: vec![None; MAX_LEVEL],
+            found: None,
+        };
+
+        let mut pred = self.head.clone();
+        for level in (0..MAX_LEVEL).rev() {
+            let mut curr = pred.next(level);
+            while let Some(node) = curr.take_if(|node| node.value() < value) {
+                curr = node.next(level);
+                pred = node;
+            }
+
+            if window.found.is_none()
+                && curr.as_ref().is_some_and(|node| node.value() == value)
+            {
+                window.found = Some(level);
+            }
+            window.preds[level] = pred.clone();
+            window.succs[level] = curr;
+        }
+
+        window
+    }
+}
+
+impl<T: PartialEq + PartialOrd> Default for ConcurrentSkipList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: PartialEq + PartialOrd> Drop for ConcurrentSkipList<T> {
+    fn drop(&mut self) {
+        // Unlink nodes one at a time so dropping a long list does not
+        // recurse down the whole chain.
+        let mut next = self.head.next[0].write().unwrap().take();
+        for level in &self.head.next {
+            level.write().unwrap().take();
+        }
+        while let Some(node) = next {
+            for level in node.next.iter().skip(1) {
+                level.write().unwrap().take();
+            }
+            next = node.next[0].write().unwrap().take();
+        }
+    }
+}
+
+/// Locks the predecessors on the lowest `levels` levels of the window,
+/// bottom up, and checks that nothing changed around them since the search.
+/// `succ_ok` says whether the node found after each predecessor is still
+/// acceptable.
+///
+/// Every thread takes node locks in decreasing order of value, which is
+/// what keeps concurrent writers from deadlocking.
+fn lock_window<'a, T>(
+    window: &'a Window<T>,
+    levels: usize,
+    succ_ok: impl Fn(&Link<T>) -> bool,
+) -> Option<Vec<MutexGuard<'a, ()>>> {
+    let mut guards = vec![];
+    let mut locked: Option<&Arc<Node<T>>> = None;
+    for level in 0..levels {
+        let pred = &window.preds[level];
+        let succ = &window.succs[level];
+        if !locked.is_some_and(|locked| Arc::ptr_eq(locked, pred)) {
+            guards.push(pred.lock());
+            locked = Some(pred);
+        }
+
+        let current = pred.next[level].read().unwrap();
+        let unchanged = match (&*current, succ) {
+            (Some(current), Some(succ)) => Arc::ptr_eq(current, succ),
+            (None, None) => true,
+            _ => false,
+        };
+        if pred.is_marked() || !succ_ok(succ) || !unchanged {
+            return None;
+        }
+    }
+
+    Some(guards)
+}
+
+/// Values of a [`ConcurrentSkipList`] in order. See
+/// [`ConcurrentSkipList::range`] for what it guarantees.
+pub struct Range<'a, T: PartialEq + PartialOrd> {
+    /// Ties the iterator to the list so the list outlives it.
+    _list: &'a ConcurrentSkipList<T>,
+    next: Link<T>,
+    end: Bound<T>,
+}
+
+impl<T: PartialEq + PartialOrd + Clone> Iterator for Range<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let node = self.next.take()?;
+            let in_range = match &self.end {
+                Bound::Included(end) => node.value() <= end,
+                Bound::Excluded(end) => node.value() < end,
+                Bound::Unbounded => true,
+            };
+            if !in_range {
+                return None;
+            }
+
+            // A removed node still leads back into the list.
+            self.next = node.next(0);
+            if node.is_marked() || !node.fully_linked.load(Ordering::Acquire) {
+                continue;
+            }
+            return Some(node.value().clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rng::Rng;
+    use std::collections::BTreeSet;
+
+    const THREADS: usize = 8;
+
+    fn create_filled() -> ConcurrentSkipList<usize> {
+        let list = ConcurrentSkipList::with_seed(7);
+        for value in [50, 25, 100, 75, 10] {
+            list.insert(value);
+        }
+        list
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<ConcurrentSkipList<usize>>();
+        assert_send_sync::<ConcurrentSkipList<String>>();
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let list = create_filled();
+        assert!(!list.insert(25));
+        assert!(list.insert(17));
+        assert_eq!(list.size(), 6);
+        assert!(list.contains(17));
+
+        assert!(list.remove(50));
+        assert!(!list.remove(50));
+        assert!(!list.remove(255));
+        assert!(!list.contains(50));
+        assert_eq!(list.size(), 5);
+    }
+
+    #[test]
+    fn test_range() {
+        let list = create_filled();
+        let values: Vec<_> = list.range(25..=75).collect();
+        assert_eq!(values, [25, 50, 75]);
+
+        let values: Vec<_> = list
+            .range((Bound::Excluded(25), Bound::Excluded(100)))
+            .collect();
+        assert_eq!(values, [50, 75]);
+
+        let values: Vec<_> = list.iter().collect();
+        assert_eq!(values, [10, 25, 50, 75, 100]);
+        assert_eq!(list.range(101..).next(), None);
+    }
+
+    #[test]
+    fn test_drop_long_list() {
+        let list = ConcurrentSkipList::with_seed(1);
+        for value in 0..200_000 {
+            list.insert(value);
+        }
+        drop(list);
+    }
+
+    #[test]
+    fn test_concurrent_insert() {
+        let list = ConcurrentSkipList::with_seed(3);
+        thread::scope(|scope| {
+            for thread in 0..THREADS {
+                let list = &list;
+                scope.spawn(move || {
+                    // Every thread inserts every value, interleaved.
+                    for value in 0..2000 {
+                        list.insert((value * 7 + thread) % 2000);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(list.size(), 2000);
+        assert!(list.iter().eq(0..2000));
+    }
+
+    #[test]
+    fn test_concurrent_insert_remove() {
+        let list = ConcurrentSkipList::with_seed(5);
+        for value in 0..4000 {
+            list.insert(value);
+        }
+
+        // Each thread owns the values congruent to its index, removing the
+        // even ones and adding a shifted copy of the odd ones.
+        thread::scope(|scope| {
+            for thread in 0..THREADS {
+                let list = &list;
+                scope.spawn(move || {
+                    for value in (thread..4000).step_by(THREADS) {
+                        if value % 2 == 0 {
+                            assert!(list.remove(value));
+                        } else {
+                            assert!(list.insert(value + 4000));
+                        }
+                    }
+                });
+            }
+        });
+
+        let expected: BTreeSet<usize> = (0..4000)
+            .filter(|value| value % 2 == 1)
+            .flat_map(|value| [value, value + 4000])
+            .collect();
+        assert_eq!(list.size(), expected.len());
+        assert!(list.iter().eq(expected.iter().copied()));
+    }
+
+    #[test]
+    fn test_concurrent_same_values() {
+        // All threads fight over the same few values. Each successful insert
+        // or remove must be matched by the final contents.
+        let list = ConcurrentSkipList::with_seed(9);
+        let balance: Vec<_> = (0..16).map(|_| AtomicUsize::new(0)).collect();
+        let removed: Vec<_> = (0..16).map(|_| AtomicUsize::new(0)).collect();
+        thread::scope(|scope| {
+            for thread in 0..THREADS {
+                let (list, balance, removed) = (&list, &balance, &removed);
+                scope.spawn(move || {
+                    let mut rng = Rng::new(thread as u64 + 1);
+                    for _ in 0..5000 {
+                        let value = (rng.next_u64() % 16) as usize;
+                        if rng.next_u64().is_multiple_of(2) {
+                            if list.insert(value) {
+                                balance[value].fetch_add(1, Ordering::Relaxed);
+                            }
+                        } else if list.remove(value) {
+                            removed[value].fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                });
+            }
+        });
+
+        for value in 0..16 {
+            let added = balance[value].load(Ordering::Relaxed);
+            let removed = removed[value].load(Ordering::Relaxed);
+            assert_eq!(added - removed, usize::from(list.contains(value)));
+        }
+        let values: Vec<_> = list.iter().collect();
+        assert!(values.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(values.len(), list.size());
+    }
+
+    #[test]
+    fn test_range_during_writes() {
+        // Values 0..1000 stay put while writers churn through the rest, so
+        // every scan must see all of them, in order, with no repeats.
+        let list = ConcurrentSkipList::with_seed(11);
+        for value in 0..1000 {
+            list.insert(value * 2);
+        }
+
+        thread::scope(|scope| {
+            for thread in 0..THREADS / 2 {
+                let list = &list;
+                scope.spawn(move || {
+                    let mut rng = Rng::new(thread as u64 + 100);
+                    for _ in 0..20_000 {
+                        let value = (rng.next_u64() % 1000) as usize * 2 + 1;
+                        match rng.next_u64().is_multiple_of(2) {
+                            true => list.insert(value),
+                            false => list.remove(value),
+                        };
+                    }
+                });
+            }
+            for _ in 0..THREADS / 2 {
+                let list = &list;
+                scope.spawn(move || {
+                    for _ in 0..50 {
+                        let values: Vec<_> = list.range(100..1900).collect();
+                        assert!(values
+                            .windows(2)
+                            .all(|pair| pair[0] < pair[1]));
+                        let stable =
+                            values.iter().filter(|value| *value % 2 == 0);
+                        assert!(stable.copied().eq((50..950).map(|v| v * 2)));
+                    }
+                });
+            }
+        });
+    }
+}
@@ -0,0 +1,248 @@
+// Copyright (C) 2025 BastIsAwesome (bastisawesomeltd@gmail.com)
+//
+// This file is part of deque.
+//
+// deque is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// deque is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with deque.  If not, see <https://www.gnu.org/licenses/>.
+use std::collections::BTreeSet;
+
+use crate::rng::Rng;
+
+/// Seed for sets that draw random priorities or levels, so that fixtures
+/// built from the same values always have the same shape.
+pub(crate) const SEED: u64 = 7;
+
+/// Values are drawn from `0..KEYS`, few enough that deletes regularly hit
+/// values that are present.
+const KEYS: u64 = 64;
+
+/// The operations the shared tests drive a set through. Every ordered set in
+/// the crate has to pass these tests, whatever its shape.
+///
+/// A set opts in by implementing this, usually through [`impl_test_set!`],
+/// and invoking [`conformance_tests!`] in its test module. Tests of a set's
+/// own invariants stay next to the set.
+pub(crate) trait TestSet: Sized {
+    /// An empty set. Sets without randomness ignore the seed.
+    fn seeded(seed: u64) -> Self;
+    fn insert(&mut self, value: usize);
+    fn delete(&mut self, value: usize);
+    /// Takes `&mut self` because looking a value up restructures some sets.
+    fn contains(&mut self, value: usize) -> bool;
+    fn min(&self) -> Option<usize>;
+    fn max(&self) -> Option<usize>;
+    fn size(&self) -> usize;
+    fn is_empty(&self) -> bool;
+    fn clear(&mut self);
+    /// Whether the set's own invariants hold.
+    fn is_valid(&self) -> bool;
+}
+
+/// Implements [`TestSet`] for a set of `usize` by forwarding to the inherent
+/// methods of the same name, with `delete` forwarded to `$delete`.
+/// `is_valid` is backed by `validate` unless a check is given.
+macro_rules! impl_test_set {
+    ($set:ident, |$seed:pat_param| $new:expr, $delete:ident) => {
+        $crate::conformance::impl_test_set!(
+            $set,
+            |$seed| $new,
+            $delete,
+            |set| $set::validate(set).is_ok()
+        );
+    };
+    (
+        $set:ident,
+        |$seed:pat_param| $new:expr,
+        $delete:ident,
+        |$valid:ident| $check:expr
+    ) => {
+        impl $crate::conformance::TestSet for $set<usize> {
+            fn seeded($seed: u64) -> Self {
+                $new
+            }
+
+            fn insert(&mut self, value: usize) {
+                $set::insert(self, value);
+            }
+
+            fn delete(&mut self, value: usize) {
+                $set::$delete(self, value);
+            }
+
+            fn contains(&mut self, value: usize) -> bool {
+                $set::contains(self, value)
+            }
+
+            fn min(&self) -> Option<usize> {
+                $set::min(self)
+            }
+
+            fn max(&self) -> Option<usize> {
+                $set::max(self)
+            }
+
+            fn size(&self) -> usize {
+                $set::size(self)
+            }
+
+            fn is_empty(&self) -> bool {
+                $set::is_empty(self)
+            }
+
+            fn clear(&mut self) {
+                $set::clear(self);
+            }
+
+            fn is_valid(&self) -> bool {
+                let $valid = self;
+                $check
+            }
+        }
+    };
+}
+pub(crate) use impl_test_set;
+
+/// Adds the shared tests for `$set`, which must implement [`TestSet`], to
+/// the surrounding test module.
+macro_rules! conformance_tests {
+    ($set:ty) => {
+        #[test]
+        fn test_conformance_empty() {
+            $crate::conformance::check_empty::<$set>();
+        }
+
+        #[test]
+        fn test_conformance_filled() {
+            $crate::conformance::check_filled::<$set>();
+        }
+
+        #[test]
+        fn test_matches_btree_set() {
+            $crate::conformance::check_matches_btree_set::<$set>();
+        }
+    };
+}
+pub(crate) use conformance_tests;
+
+/// An empty set seeded with [`SEED`].
+pub(crate) fn empty<S: TestSet>() -> S {
+    S::seeded(SEED)
+}
+
+/// A set holding `values`, inserted in order.
+pub(crate) fn from_values<S: TestSet>(
+    values: impl IntoIterator<Item = usize>,
+) -> S {
+    let mut set = empty::<S>();
+    for value in values {
+        set.insert(value);
+    }
+    set
+}
+
+/// A root with one child on either side, in any of the binary trees.
+pub(crate) fn filled<S: TestSet>() -> S {
+    from_values([50, 25, 100])
+}
+
+/// The values of a complete tree three levels deep, root first.
+pub(crate) fn filled_wide<S: TestSet>() -> S {
+    from_values([50, 25, 100, 17, 42, 75, 150])
+}
+
+pub(crate) fn check_empty<S: TestSet>() {
+    let mut set = empty::<S>();
+    assert!(set.is_empty());
+    assert_eq!(set.size(), 0);
+    assert_eq!(set.min(), None);
+    assert_eq!(set.max(), None);
+    assert!(!set.contains(1));
+    set.delete(1);
+    assert!(set.is_empty());
+    assert!(set.is_valid());
+}
+
+pub(crate) fn check_filled<S: TestSet>() {
+    let mut set = filled::<S>();
+    assert_eq!(set.size(), 3);
+    assert!(!set.is_empty());
+    assert!(set.contains(25));
+    assert!(set.contains(50));
+    assert!(set.contains(100));
+    assert!(!set.contains(255));
+    assert_eq!(set.min(), Some(25));
+    assert_eq!(set.max(), Some(100));
+
+    // Duplicates and missing values change nothing.
+    set.insert(50);
+    set.delete(255);
+    assert_eq!(set.size(), 3);
+
+    set.insert(255);
+    assert!(set.contains(255));
+    assert_eq!(set.max(), Some(255));
+
+    set.delete(25);
+    assert!(!set.contains(25));
+    set.delete(50);
+    assert!(!set.contains(50));
+    assert!(set.contains(100));
+    assert_eq!(set.size(), 2);
+    assert_eq!(set.min(), Some(100));
+    assert!(set.is_valid());
+
+    set.clear();
+    assert!(set.is_empty());
+    assert_eq!(set.size(), 0);
+    assert_eq!(set.min(), None);
+}
+
+/// Applies the same 400 random inserts and deletes to a new set and to a
+/// `BTreeSet`, checking after every step that the set agrees and is valid.
+pub(crate) fn random_ops<S: TestSet>(seed: u64) -> (S, BTreeSet<usize>) {
+    let mut rng = Rng::new(seed);
+    let mut set = S::seeded(seed);
+    let mut expected = BTreeSet::new();
+
+    for _ in 0..400 {
+        let value = (rng.next_u64() % KEYS) as usize;
+        if rng.next_u64().is_multiple_of(3) {
+            set.delete(value);
+            expected.remove(&value);
+        } else {
+            set.insert(value);
+            expected.insert(value);
+        }
+
+        assert!(set.is_valid(), "seed {seed}");
+        assert_eq!(set.size(), expected.len(), "seed {seed}");
+        assert_eq!(set.contains(value), expected.contains(&value));
+    }
+
+    (set, expected)
+}
+
+pub(crate) fn check_matches_btree_set<S: TestSet>() {
+    for seed in 1..=64 {
+        let (mut set, expected) = random_ops::<S>(seed);
+        for value in 0..KEYS as usize {
+            assert_eq!(
+                set.contains(value),
+                expected.contains(&value),
+                "seed {seed}"
+            );
+        }
+        assert_eq!(set.min(), expected.first().copied(), "seed {seed}");
+        assert_eq!(set.max(), expected.last().copied(), "seed {seed}");
+    }
+}
@@ -0,0 +1,515 @@
+// Copyright (C) 2025 BastIsAwesome (bastisawesomeltd@gmail.com)
+//
+// This file is part of deque.
+//
+// deque is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// deque is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with deque.  If not, see <https://www.gnu.org/licenses/>.
+use std::ops::{Bound, RangeBounds};
+
+use crate::rng::Rng;
+use crate::validate::{
+    self, Direction, InvariantViolation, TreeStats, ViolationKind,
+};
+
+type Link<T> = Option<Box<ImplicitNode<T>>>;
+
+/// A sequence stored as a treap keyed by position. A node's index is the
+/// number of nodes before it in order, which is worked out from subtree sizes
+/// on the way down, so no keys are stored at all.
+///
+/// Inserting, removing, splitting, concatenating and reversing a range all
+/// take expected O(log n). Reversal is lazy: the range is cut out, flagged
+/// and put back, and the flag is pushed down only as nodes are visited.
+pub struct ImplicitTreap<T> {
+    root: Link<T>,
+    rng: Rng,
+}
+
+/// The implicit treap is the usual backing structure for a rope.
+pub type Rope<T> = ImplicitTreap<T>;
+
+impl<T> ImplicitTreap<T> {
+    /// Seeds the priorities from per-process randomness.
+    pub fn new() -> Self {
+        Self {
+            root: None,
+            rng: Rng::from_entropy(),
+        }
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            root: None,
+            rng: Rng::new(seed),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        let mut node = self.root.as_deref()?;
+        let mut index = index;
+        // Pending reversals above `node`, applied as we read its children
+        // instead of being pushed down, so lookups do not need `&mut self`.
+        let mut reversed = false;
+        loop {
+            reversed ^= node.reversed;
+            let (before, after) = match reversed {
+                true => (&node.right, &node.left),
+                false => (&node.left, &node.right),
+            };
+
+            let before_size = size(before);
+            if index < before_size {
+                node = before.as_deref()?;
+            } else if index == before_size {
+                return Some(&node.data);
+            } else {
+                index -= before_size + 1;
+                node = after.as_deref()?;
+            }
+        }
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        self.insert_at(self.len(), value);
+    }
+
+    /// Panics if `index > len`.
+    pub fn insert_at(&mut self, index: usize, value: T) {
+        assert!(index <= self.len(), "insertion index out of bounds");
+
+        let node = Box::new(ImplicitNode::new(value, self.rng.next_u64()));
+        let (left, right) = split(self.root.take(), index);
+        self.root = merge(merge(left, Some(node)), right);
+    }
+
+    /// Panics if `index >= len`.
+    pub fn remove_at(&mut self, index: usize) -> T {
+        assert!(index < self.len(), "removal index out of bounds");
+
+        let (left, right) = split(self.root.take(), index);
+        let (node, right) = split(right, 1);
+        self.root = merge(left, right);
+        node.unwrap().data
+    }
+
+    /// Splits the sequence into the first `index` elements and the rest.
+    ///
+    /// Panics if `index > len`.
+    pub fn split_at(mut self, index: usize) -> (Self, Self) {
+        assert!(index <= self.len(), "split index out of bounds");
+
+        let (left, right) = split(self.root.take(), index);
+        let right_rng = Rng::new(self.rng.next_u64());
+        (
+            Self {
+                root: left,
+                rng: self.rng,
+            },
+            Self {
+                root: right,
+                rng: right_rng,
+            },
+        )
+    }
+
+    /// Appends `right` to `left`. The result keeps drawing priorities from
+    /// `left`'s generator.
+    pub fn concat(mut left: Self, mut right: Self) -> Self {
+        left.root = merge(left.root.take(), right.root.take());
+        left
+    }
+
+    /// Reverses the elements in `range`.
+    ///
+    /// Panics if the range is out of bounds or starts after it ends.
+    pub fn reverse_range<R: RangeBounds<usize>>(&mut self, range: R) {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => {
+                start.checked_add(1).expect("range overflow")
+            },
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => {
+                end.checked_add(1).expect("range overflow")
+            },
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len(),
+        };
+        assert!(start <= end, "range starts after it ends");
+        assert!(end <= self.len(), "range end out of bounds");
+
+        let (left, rest) = split(self.root.take(), start);
+        let (mut middle, right) = split(rest, end - start);
+        if let Some(middle) = &mut middle {
+            middle.reversed ^= true;
+        }
+        self.root = merge(merge(left, middle), right);
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        let mut iter = Iter {
+            stack: vec![],
+            remaining: self.len(),
+        };
+        iter.push_before(self.root.as_deref(), false);
+        iter
+    }
+
+    /// Checks that no child outranks its parent in priority and the subtree
+    /// size stored on every node.
+    pub fn validate(&self) -> Result<TreeStats, InvariantViolation> {
+        let Some(root) = &self.root else {
+            return Ok(TreeStats::default());
+        };
+
+        let mut stats = TreeStats::default();
+        check_node(root, &mut Vec::new(), &mut stats)?;
+        Ok(stats)
+    }
+}
+
+impl<T> Default for ImplicitTreap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<T> for ImplicitTreap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut treap = Self::new();
+        for value in iter {
+            treap.push_back(value);
+        }
+        treap
+    }
+}
+
+/// In-order iterator over an [`ImplicitTreap`].
+pub struct Iter<'a, T> {
+    /// Nodes still to be yielded, each with whether its subtree is read
+    /// mirrored.
+    stack: Vec<(&'a ImplicitNode<T>, bool)>,
+    remaining: usize,
+}
+
+impl<'a, T> Iter<'a, T> {
+    /// Pushes the node and the chain of children leading to its first
+    /// element.
+    fn push_before(&mut self, node: Option<&'a ImplicitNode<T>>, flip: bool) {
+        let mut node = node;
+        let mut flip = flip;
+        while let Some(current) = node {
+            flip ^= current.reversed;
+            self.stack.push((current, flip));
+            node = match flip {
+                true => current.right.as_deref(),
+                false => current.left.as_deref(),
+            };
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node, flip) = self.stack.pop()?;
+        let after = match flip {
+            true => node.left.as_deref(),
+            false => node.right.as_deref(),
+        };
+        self.push_before(after, flip);
+        self.remaining -= 1;
+        Some(&node.data)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+fn size<T>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+/// Splits the subtree into its first `index` elements and the rest.
+fn split<T>(link: Link<T>, index: usize) -> (Link<T>, Link<T>) {
+    let Some(mut node) = link else {
+        return (None, None);
+    };
+
+    node.push_down();
+    let left_size = size(&node.left);
+    if index <= left_size {
+        let (left, right) = split(node.left.take(), index);
+        node.left = right;
+        node.update_size();
+        (left, Some(node))
+    } else {
+        let (left, right) = split(node.right.take(), index - left_size - 1);
+        node.right = left;
+        node.update_size();
+        (Some(node), right)
+    }
+}
+
+/// Joins two subtrees, `left` coming first. The root with the higher
+/// priority stays on top.
+fn merge<T>(left: Link<T>, right: Link<T>) -> Link<T> {
+    match (left, right) {
+        (None, right) => right,
+        (left, None) => left,
+        (Some(mut left), Some(mut right)) => {
+            if left.priority > right.priority {
+                left.push_down();
+                left.right = merge(left.right.take(), Some(right));
+                left.update_size();
+                Some(left)
+            } else {
+                right.push_down();
+                right.left = merge(Some(left), right.left.take());
+                right.update_size();
+                Some(right)
+            }
+        },
+    }
+}
+
+fn check_node<T>(
+    node: &ImplicitNode<T>,
+    path: &mut Vec<Direction>,
+    stats: &mut TreeStats,
+) -> Result<usize, InvariantViolation> {
+    stats.size += 1;
+    stats.height = stats.height.max(path.len() + 1);
+
+    let mut size = 1;
+    for (direction, child) in [
+        (Direction::Left, &node.left),
+        (Direction::Right, &node.right),
+    ] {
+        let Some(child) = child else {
+            continue;
+        };
+
+        path.push(direction);
+        if child.priority > node.priority {
+            return Err(InvariantViolation::new(
+                path.clone(),
+                ViolationKind::HeapOrder,
+            ));
+        }
+        size += check_node(child, path, stats)?;
+        path.pop();
+    }
+
+    validate::check_size(path, node.size, size)?;
+
+    Ok(size)
+}
+
+struct ImplicitNode<T> {
+    left: Link<T>,
+    right: Link<T>,
+    data: T,
+    priority: u64,
+    /// Number of nodes in the subtree rooted here, this one included.
+    size: usize,
+    /// The subtree below this node is stored mirrored and still has to be
+    /// flipped back.
+    reversed: bool,
+}
+
+impl<T> ImplicitNode<T> {
+    fn new(data: T, priority: u64) -> Self {
+        Self {
+            left: None,
+            right: None,
+            data,
+            priority,
+            size: 1,
+            reversed: false,
+        }
+    }
+
+    fn update_size(&mut self) {
+        self.size = 1 + size(&self.left) + size(&self.right);
+    }
+
+    /// Applies a pending reversal to this node and hands it to its children.
+    fn push_down(&mut self) {
+        if !self.reversed {
+            return;
+        }
+
+        std::mem::swap(&mut self.left, &mut self.right);
+        for child in [&mut self.left, &mut self.right].into_iter().flatten() {
+            child.reversed ^= true;
+        }
+        self.reversed = false;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    type URope = ImplicitTreap<usize>;
+
+    fn create_filled() -> URope {
+        let mut rope = URope::with_seed(7);
+        for value in 0..10 {
+            rope.push_back(value);
+        }
+        rope
+    }
+
+    fn values(rope: &URope) -> Vec<usize> {
+        rope.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_insert_at() {
+        let mut rope = create_filled();
+        rope.insert_at(0, 100);
+        rope.insert_at(5, 200);
+        rope.insert_at(12, 300);
+        assert_eq!(
+            values(&rope),
+            [100, 0, 1, 2, 3, 200, 4, 5, 6, 7, 8, 9, 300]
+        );
+        assert_eq!(rope.validate().unwrap().size, 13);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_insert_at_out_of_bounds() {
+        let mut rope = create_filled();
+        rope.insert_at(11, 100);
+    }
+
+    #[test]
+    fn test_remove_at() {
+        let mut rope = create_filled();
+        assert_eq!(rope.remove_at(0), 0);
+        assert_eq!(rope.remove_at(4), 5);
+        assert_eq!(rope.remove_at(7), 9);
+        assert_eq!(values(&rope), [1, 2, 3, 4, 6, 7, 8]);
+        assert!(rope.validate().is_ok());
+    }
+
+    #[test]
+    fn test_get() {
+        let rope = create_filled();
+        assert_eq!(rope.get(0), Some(&0));
+        assert_eq!(rope.get(9), Some(&9));
+        assert_eq!(rope.get(10), None);
+        assert_eq!(URope::with_seed(0).get(0), None);
+    }
+
+    #[test]
+    fn test_split_at_concat() {
+        let (left, right) = create_filled().split_at(4);
+        assert_eq!(values(&left), [0, 1, 2, 3]);
+        assert_eq!(values(&right), [4, 5, 6, 7, 8, 9]);
+
+        let rope = URope::concat(right, left);
+        assert_eq!(values(&rope), [4, 5, 6, 7, 8, 9, 0, 1, 2, 3]);
+        assert_eq!(rope.len(), 10);
+        assert!(rope.validate().is_ok());
+    }
+
+    #[test]
+    fn test_reverse_range() {
+        let mut rope = create_filled();
+        rope.reverse_range(2..6);
+        assert_eq!(values(&rope), [0, 1, 5, 4, 3, 2, 6, 7, 8, 9]);
+        assert_eq!(rope.get(2), Some(&5));
+
+        rope.reverse_range(..);
+        assert_eq!(values(&rope), [9, 8, 7, 6, 2, 3, 4, 5, 1, 0]);
+
+        rope.reverse_range(3..=4);
+        assert_eq!(values(&rope), [9, 8, 7, 2, 6, 3, 4, 5, 1, 0]);
+        assert!(rope.validate().is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "range overflow")]
+    fn test_reverse_range_overflow() {
+        let mut rope = create_filled();
+        rope.reverse_range(..=usize::MAX);
+    }
+
+    #[test]
+    fn test_matches_vec() {
+        for seed in 1..=32 {
+            let mut rng = Rng::new(seed);
+            let mut rope = URope::with_seed(seed);
+            let mut vec = Vec::new();
+
+            for step in 0..300 {
+                let len = vec.len() as u64;
+                match rng.next_u64() % 4 {
+                    0 if len > 0 => {
+                        let index = (rng.next_u64() % len) as usize;
+                        assert_eq!(rope.remove_at(index), vec.remove(index));
+                    },
+                    1 if len > 0 => {
+                        let a = (rng.next_u64() % (len + 1)) as usize;
+                        let b = (rng.next_u64() % (len + 1)) as usize;
+                        let (start, end) = (a.min(b), a.max(b));
+                        rope.reverse_range(start..end);
+                        vec[start..end].reverse();
+                    },
+                    _ => {
+                        let index = (rng.next_u64() % (len + 1)) as usize;
+                        rope.insert_at(index, step);
+                        vec.insert(index, step);
+                    },
+                }
+
+                assert_eq!(rope.len(), vec.len());
+                assert!(rope.validate().is_ok(), "seed {seed}");
+            }
+
+            assert_eq!(values(&rope), vec, "seed {seed}");
+            for (index, value) in vec.iter().enumerate() {
+                assert_eq!(rope.get(index), Some(value));
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_iter() {
+        let rope: URope = (0..5).collect();
+        assert_eq!(values(&rope), [0, 1, 2, 3, 4]);
+        assert_eq!(rope.iter().len(), 5);
+    }
+}
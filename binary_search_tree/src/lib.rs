@@ -14,6 +14,28 @@
 //
 // You should have received a copy of the GNU General Public License
 // along with deque.  If not, see <https://www.gnu.org/licenses/>.
+//! Ordered sets built on binary search trees, B-trees and skip lists.
+//!
+//! Every set ignores values it already holds, and operations on values it
+//! does not hold leave it unchanged.
+pub mod aa_bst;
 pub mod avl_bst;
 pub mod bst;
+pub mod btree;
+pub mod codec;
+pub mod concurrent_skip_list;
+#[cfg(test)]
+mod conformance;
+pub mod implicit_treap;
+pub mod llrb_bst;
+mod node;
 pub mod rb_bst;
+mod render;
+mod rng;
+pub mod scapegoat;
+pub mod skip_list;
+pub mod splay;
+pub mod trace;
+pub mod treap;
+pub mod validate;
+pub mod wbt;
@@ -0,0 +1,540 @@
+// Copyright (C) 2025 BastIsAwesome (bastisawesomeltd@gmail.com)
+//
+// This file is part of deque.
+//
+// deque is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// deque is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with deque.  If not, see <https://www.gnu.org/licenses/>.
+use std::fmt::Display;
+
+use crate::node::BinaryNode;
+use crate::render;
+use crate::trace::{NoObserver, TreeObserver};
+use crate::validate::{
+    self, Direction, InvariantViolation, TreeStats, ViolationKind,
+};
+
+type Link<T> = Option<Box<LlrbNode<T>>>;
+
+/// Sedgewick's left-leaning red-black tree.
+///
+/// Each node records the colour of the link from its parent. Red links only
+/// ever lean left and never come two in a row, which makes the tree a binary
+/// encoding of a 2-3 tree: a node with a red left child is a 3-node. Every
+/// path from the root down to a missing child crosses the same number of
+/// black links, so no path is more than twice as long as another.
+///
+/// Insertions and deletions report each step they take, rotations
+/// included, to the tree's [`TreeObserver`].
+pub struct LlrbTree<T: PartialEq + PartialOrd, O = NoObserver> {
+    root: Link<T>,
+    len: usize,
+    observer: O,
+}
+
+impl<T: PartialEq + PartialOrd> LlrbTree<T> {
+    pub fn new(data: Option<T>) -> Self {
+        let mut tree = Self::with_observer(NoObserver);
+        if let Some(data) = data {
+            tree.insert(data);
+        }
+        tree
+    }
+}
+
+impl<T: PartialEq + PartialOrd, O: TreeObserver<T>> LlrbTree<T, O> {
+    pub fn with_observer(observer: O) -> Self {
+        Self {
+            root: None,
+            len: 0,
+            observer,
+        }
+    }
+
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    pub fn insert(&mut self, value: T) {
+        let mut inserted = false;
+        let observer = &mut self.observer;
+        let mut root = insert(self.root.take(), value, &mut inserted, observer);
+        root.red = false;
+        self.root = Some(root);
+
+        if inserted {
+            self.len += 1;
+        }
+    }
+
+    pub fn delete(&mut self, value: T) {
+        if !self.contains_ref(&value) {
+            return;
+        }
+
+        // The descent needs the current node or one of its children to be
+        // red, so the root starts out red unless a child already is.
+        let mut root = self.root.take().unwrap();
+        if !is_red(&root.left) && !is_red(&root.right) {
+            root.red = true;
+        }
+
+        self.root = delete(root, &value, &mut self.observer);
+        if let Some(root) = &mut self.root {
+            root.red = false;
+        }
+        self.len -= 1;
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.contains_ref(&value)
+    }
+
+    pub fn min(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        let mut node = self.root.as_deref()?;
+        while let Some(left) = &node.left {
+            node = left;
+        }
+        Some(node.data.clone())
+    }
+
+    pub fn max(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        let mut node = self.root.as_deref()?;
+        while let Some(right) = &node.right {
+            node = right;
+        }
+        Some(node.data.clone())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn size(&self) -> usize {
+        self.len
+    }
+
+    pub fn height(&self) -> usize {
+        validate::height(self.root.as_deref())
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+
+    /// Checks the search tree order and the stored size, that red links lean
+    /// left and never follow each other, and that every path has the same
+    /// number of black links.
+    pub fn validate(&self) -> Result<TreeStats, InvariantViolation> {
+        let Some(root) = &self.root else {
+            return Ok(TreeStats::default());
+        };
+
+        let stats = validate::check_order(root.as_ref())?;
+        validate::check_size(&[], self.len, stats.size)?;
+
+        check_colors(root, &mut Vec::new())?;
+        Ok(stats)
+    }
+
+    fn contains_ref(&self, value: &T) -> bool {
+        let mut node = self.root.as_deref();
+        while let Some(current) = node {
+            if current.data == *value {
+                return true;
+            }
+
+            node = if *value < current.data {
+                current.left.as_deref()
+            } else {
+                current.right.as_deref()
+            };
+        }
+
+        false
+    }
+}
+
+impl<T: PartialEq + PartialOrd + Display, O> LlrbTree<T, O> {
+    pub fn to_dot(&self) -> String {
+        render::to_dot("LlrbTree", self.root.as_deref())
+    }
+
+    pub fn render_ascii(&self) -> String {
+        render::render_ascii(self.root.as_deref())
+    }
+}
+
+fn is_red<T>(link: &Link<T>) -> bool {
+    link.as_ref().is_some_and(|node| node.red)
+}
+
+fn rotate_left<T, O: TreeObserver<T>>(
+    mut node: Box<LlrbNode<T>>,
+    observer: &mut O,
+) -> Box<LlrbNode<T>> {
+    observer.on_rotate(Direction::Left, &node.data);
+    let mut right = node.right.take().unwrap();
+    node.right = right.left.take();
+    right.red = node.red;
+    node.red = true;
+    right.left = Some(node);
+    right
+}
+
+fn rotate_right<T, O: TreeObserver<T>>(
+    mut node: Box<LlrbNode<T>>,
+    observer: &mut O,
+) -> Box<LlrbNode<T>> {
+    observer.on_rotate(Direction::Right, &node.data);
+    let mut left = node.left.take().unwrap();
+    node.left = left.right.take();
+    left.red = node.red;
+    node.red = true;
+    left.right = Some(node);
+    left
+}
+
+/// Splits a temporary 4-node on the way up, or builds one on the way down.
+fn flip_colors<T>(node: &mut LlrbNode<T>) {
+    node.red = !node.red;
+    for child in [&mut node.left, &mut node.right].into_iter().flatten() {
+        child.red = !child.red;
+    }
+}
+
+/// Restores the left-leaning invariants at `node` after a change below it.
+fn balance<T, O: TreeObserver<T>>(
+    mut node: Box<LlrbNode<T>>,
+    observer: &mut O,
+) -> Box<LlrbNode<T>> {
+    if is_red(&node.right) && !is_red(&node.left) {
+        node = rotate_left(node, observer);
+    }
+    if is_red(&node.left) && is_red(&node.left.as_ref().unwrap().left) {
+        node = rotate_right(node, observer);
+    }
+    if is_red(&node.left) && is_red(&node.right) {
+        flip_colors(&mut node);
+    }
+    node
+}
+
+fn insert<T: PartialOrd, O: TreeObserver<T>>(
+    link: Link<T>,
+    value: T,
+    inserted: &mut bool,
+    observer: &mut O,
+) -> Box<LlrbNode<T>> {
+    let Some(mut node) = link else {
+        observer.on_link(&value);
+        *inserted = true;
+        return Box::new(LlrbNode::new(value));
+    };
+
+    observer.on_compare(&value, &node.data);
+    if value < node.data {
+        observer.on_descend(Direction::Left);
+        let left = insert(node.left.take(), value, inserted, observer);
+        node.left = Some(left);
+    } else if value > node.data {
+        observer.on_descend(Direction::Right);
+        let right = insert(node.right.take(), value, inserted, observer);
+        node.right = Some(right);
+    } else {
+        return node;
+    }
+
+    balance(node, observer)
+}
+
+/// Makes the left child or one of its children red, assuming `node` is red
+/// and both its children are black.
+fn move_red_left<T, O: TreeObserver<T>>(
+    mut node: Box<LlrbNode<T>>,
+    observer: &mut O,
+) -> Box<LlrbNode<T>> {
+    flip_colors(&mut node);
+    let right = node.right.as_ref().unwrap();
+    if is_red(&right.left) {
+        let right = node.right.take().unwrap();
+        node.right = Some(rotate_right(right, observer));
+        node = rotate_left(node, observer);
+        flip_colors(&mut node);
+    }
+    node
+}
+
+/// Makes the right child or one of its children red, assuming `node` is red
+/// and both its children are black.
+fn move_red_right<T, O: TreeObserver<T>>(
+    mut node: Box<LlrbNode<T>>,
+    observer: &mut O,
+) -> Box<LlrbNode<T>> {
+    flip_colors(&mut node);
+    if is_red(&node.left.as_ref().unwrap().left) {
+        node = rotate_right(node, observer);
+        flip_colors(&mut node);
+    }
+    node
+}
+
+/// Removes the smallest value of the subtree, returning what is left of it
+/// and the value.
+fn take_min<T, O: TreeObserver<T>>(
+    mut node: Box<LlrbNode<T>>,
+    observer: &mut O,
+) -> (Link<T>, T) {
+    if node.left.is_none() {
+        return (None, node.data);
+    }
+
+    if !is_red(&node.left) && !is_red(&node.left.as_ref().unwrap().left) {
+        node = move_red_left(node, observer);
+    }
+    observer.on_descend(Direction::Left);
+    let (left, min) = take_min(node.left.take().unwrap(), observer);
+    node.left = left;
+    (Some(balance(node, observer)), min)
+}
+
+/// Removes `value`, which must be in the subtree. The descent keeps the
+/// current node or its child on the search path red, so the node finally
+/// removed is never a lone black node.
+fn delete<T: PartialOrd, O: TreeObserver<T>>(
+    mut node: Box<LlrbNode<T>>,
+    value: &T,
+    observer: &mut O,
+) -> Link<T> {
+    observer.on_compare(value, &node.data);
+    if *value < node.data {
+        if !is_red(&node.left) && !is_red(&node.left.as_ref().unwrap().left) {
+            node = move_red_left(node, observer);
+        }
+        observer.on_descend(Direction::Left);
+        node.left = delete(node.left.take().unwrap(), value, observer);
+    } else {
+        if is_red(&node.left) {
+            node = rotate_right(node, observer);
+        }
+        if *value == node.data && node.right.is_none() {
+            observer.on_unlink(&node.data);
+            return None;
+        }
+        if !is_red(&node.right) && !is_red(&node.right.as_ref().unwrap().left) {
+            node = move_red_right(node, observer);
+        }
+        if *value == node.data {
+            // Replace the value with its successor, the smallest value on
+            // its right.
+            observer.on_unlink(&node.data);
+            observer.on_descend(Direction::Right);
+            let (right, min) = take_min(node.right.take().unwrap(), observer);
+            node.right = right;
+            node.data = min;
+        } else {
+            observer.on_descend(Direction::Right);
+            node.right = delete(node.right.take().unwrap(), value, observer);
+        }
+    }
+
+    Some(balance(node, observer))
+}
+
+/// Checks the colour invariants below `node`, returning the number of black
+/// links on every path from it down to a missing child.
+fn check_colors<T>(
+    node: &LlrbNode<T>,
+    path: &mut Vec<Direction>,
+) -> Result<usize, InvariantViolation> {
+    if is_red(&node.right) {
+        path.push(Direction::Right);
+        return Err(InvariantViolation::new(
+            path.clone(),
+            ViolationKind::RedRightLink,
+        ));
+    }
+    if node.red && is_red(&node.left) {
+        path.push(Direction::Left);
+        return Err(InvariantViolation::new(
+            path.clone(),
+            ViolationKind::DoubleRed,
+        ));
+    }
+
+    let mut heights = [0; 2];
+    for (height, (direction, child)) in heights.iter_mut().zip([
+        (Direction::Left, &node.left),
+        (Direction::Right, &node.right),
+    ]) {
+        let Some(child) = child else {
+            continue;
+        };
+
+        path.push(direction);
+        *height = check_colors(child, path)?;
+        path.pop();
+    }
+
+    let [left, right] = heights;
+    if left != right {
+        return Err(InvariantViolation::new(
+            path.clone(),
+            ViolationKind::BlackHeight { left, right },
+        ));
+    }
+
+    Ok(left + usize::from(!node.red))
+}
+
+struct LlrbNode<T> {
+    data: T,
+    /// Colour of the link from the parent.
+    red: bool,
+    left: Link<T>,
+    right: Link<T>,
+}
+
+impl<T> LlrbNode<T> {
+    /// New nodes are always linked in red.
+    fn new(data: T) -> Self {
+        Self {
+            data,
+            red: true,
+            left: None,
+            right: None,
+        }
+    }
+}
+
+impl<T> BinaryNode for LlrbNode<T> {
+    type Value = T;
+
+    fn value(&self) -> &T {
+        &self.data
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::conformance::{conformance_tests, empty, filled, impl_test_set};
+    use crate::trace::{TraceEvent, TraceRecorder};
+
+    impl_test_set!(LlrbTree, |_| LlrbTree::new(None), delete);
+    conformance_tests!(LlrbTree<usize>);
+
+    #[test]
+    fn test_insert() {
+        let mut tree: LlrbTree<usize> = empty();
+        tree.insert(16);
+        assert_eq!(tree.root.as_ref().unwrap().data, 16);
+
+        // Ascending values keep rotating the red right link over to the left.
+        tree.insert(32);
+        assert_eq!(tree.root.as_ref().unwrap().data, 32);
+        assert!(tree.root.as_ref().unwrap().left.as_ref().unwrap().red);
+
+        tree.insert(64);
+        assert_eq!(tree.root.as_ref().unwrap().data, 32);
+        assert_eq!(tree.height(), 2);
+        assert!(tree.validate().is_ok());
+    }
+
+    #[test]
+    fn test_sorted_input_is_balanced() {
+        let mut tree: LlrbTree<usize> = empty();
+        for value in 0..1023 {
+            tree.insert(value);
+        }
+        // At most twice the height of a perfectly balanced tree.
+        assert!(tree.height() <= 20);
+        assert_eq!(tree.validate().unwrap().size, 1023);
+    }
+
+    #[test]
+    fn test_validate_empty() {
+        assert_eq!(
+            empty::<LlrbTree<usize>>().validate().unwrap(),
+            TreeStats::default()
+        );
+    }
+
+    #[test]
+    fn test_validate_red_right_link() {
+        let mut tree: LlrbTree<usize> = filled();
+        tree.root.as_mut().unwrap().right.as_mut().unwrap().red = true;
+        let violation = tree.validate().unwrap_err();
+        assert_eq!(violation.path, vec![Direction::Right]);
+        assert_eq!(violation.kind, ViolationKind::RedRightLink);
+    }
+
+    #[test]
+    fn test_validate_black_height() {
+        let mut tree: LlrbTree<usize> = filled();
+        tree.root.as_mut().unwrap().right.as_mut().unwrap().right =
+            Some(Box::new(LlrbNode {
+                red: false,
+                ..LlrbNode::new(200)
+            }));
+        tree.len = 4;
+        let violation = tree.validate().unwrap_err();
+        assert_eq!(violation.path, vec![Direction::Right]);
+        assert_eq!(
+            violation.kind,
+            ViolationKind::BlackHeight { left: 0, right: 1 }
+        );
+    }
+
+    #[test]
+    fn test_observer_rotate() {
+        let mut tree = LlrbTree::with_observer(TraceRecorder::new());
+        tree.insert(1);
+        tree.observer_mut().clear();
+
+        // A red right link is rotated to lean left.
+        tree.insert(2);
+        assert_eq!(
+            tree.observer().events(),
+            [
+                TraceEvent::Compare { value: 2, node: 1 },
+                TraceEvent::Descend {
+                    direction: Direction::Right,
+                },
+                TraceEvent::Link { value: 2 },
+                TraceEvent::Rotate {
+                    direction: Direction::Left,
+                    pivot: 1,
+                },
+            ]
+        );
+    }
+}
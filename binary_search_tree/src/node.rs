@@ -0,0 +1,25 @@
+// Copyright (C) 2025 BastIsAwesome (bastisawesomeltd@gmail.com)
+//
+// This file is part of deque.
+//
+// deque is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// deque is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with deque.  If not, see <https://www.gnu.org/licenses/>.
+/// Read-only view of a binary tree node, letting the validation and rendering
+/// helpers work across the different node types.
+pub(crate) trait BinaryNode {
+    type Value;
+
+    fn value(&self) -> &Self::Value;
+    fn left(&self) -> Option<&Self>;
+    fn right(&self) -> Option<&Self>;
+}
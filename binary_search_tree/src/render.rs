@@ -0,0 +1,214 @@
+// Copyright (C) 2025 BastIsAwesome (bastisawesomeltd@gmail.com)
+//
+// This file is part of deque.
+//
+// deque is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// deque is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with deque.  If not, see <https://www.gnu.org/licenses/>.
+use std::fmt::{Display, Write};
+
+use crate::node::BinaryNode;
+
+/// Marker drawn in place of a missing child whose sibling exists.
+const NULL_MARKER: &str = "*";
+
+/// Emits a Graphviz digraph of the tree. Missing children are drawn as point
+/// shaped null leaves so the left/right shape stays visible.
+///
+/// Backs the `to_dot` method of every binary tree in the crate, which names
+/// the graph after the tree's type.
+pub(crate) fn to_dot<N: BinaryNode>(name: &str, root: Option<&N>) -> String
+where
+    N::Value: Display,
+{
+    let mut out = format!("digraph {name} {{\n");
+    let mut next_id = 0;
+    // Pre-order with an explicit stack, so degenerate trees cannot overflow
+    // the call stack. A node's edge from its parent is queued below its
+    // children, so it is written once the whole subtree has been.
+    let mut stack = vec![];
+    if let Some(root) = root {
+        stack.push(DotWork::Node(root, None));
+    }
+    while let Some(work) = stack.pop() {
+        match work {
+            DotWork::Node(node, parent) => {
+                let id = next_id;
+                next_id += 1;
+                let label = node
+                    .value()
+                    .to_string()
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\"");
+                writeln!(out, "    n{id} [label=\"{label}\"];").unwrap();
+
+                if let Some(parent) = parent {
+                    stack.push(DotWork::Edge(parent, id));
+                }
+                for child in [node.right(), node.left()] {
+                    stack.push(match child {
+                        Some(child) => DotWork::Node(child, Some(id)),
+                        None => DotWork::Null(id),
+                    });
+                }
+            },
+            DotWork::Null(parent) => {
+                let id = next_id;
+                next_id += 1;
+                writeln!(out, "    n{id} [shape=point];").unwrap();
+                writeln!(out, "    n{parent} -> n{id};").unwrap();
+            },
+            DotWork::Edge(parent, id) => {
+                writeln!(out, "    n{parent} -> n{id};").unwrap();
+            },
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// A step of [`to_dot`]'s walk. Nodes remember their parent's id so that the
+/// edge between them can be written after the node's subtree.
+enum DotWork<'a, N> {
+    Node(&'a N, Option<usize>),
+    Null(usize),
+    Edge(usize, usize),
+}
+
+/// Draws the tree top-down, one line per row of text. A missing child is
+/// drawn as `*` when its sibling exists.
+///
+/// Backs the `render_ascii` method of every binary tree in the crate. The
+/// diagrams in the tests are drawn the same way.
+pub(crate) fn render_ascii<N: BinaryNode>(root: Option<&N>) -> String
+where
+    N::Value: Display,
+{
+    let Some(root) = root else {
+        return String::new();
+    };
+
+    let mut out = String::new();
+    for line in layout(root).lines {
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// A rendered subtree. Every line is exactly `width` characters wide and the
+/// root's label is centred on column `middle`.
+struct Block {
+    lines: Vec<String>,
+    width: usize,
+    middle: usize,
+}
+
+/// Lays the tree out post-order with an explicit stack, so degenerate trees
+/// cannot overflow the call stack. Finished subtrees wait on `blocks` until
+/// their parent joins them.
+fn layout<N: BinaryNode>(root: &N) -> Block
+where
+    N::Value: Display,
+{
+    let mut stack = vec![LayoutWork::Visit(Some(root))];
+    let mut blocks = vec![];
+    while let Some(work) = stack.pop() {
+        match work {
+            LayoutWork::Visit(None) => {
+                blocks.push(leaf(NULL_MARKER.to_string()))
+            },
+            LayoutWork::Visit(Some(node)) => {
+                if node.left().is_none() && node.right().is_none() {
+                    blocks.push(leaf(label(node)));
+                    continue;
+                }
+
+                stack.push(LayoutWork::Join(node));
+                stack.push(LayoutWork::Visit(node.right()));
+                stack.push(LayoutWork::Visit(node.left()));
+            },
+            LayoutWork::Join(node) => {
+                let right = blocks.pop().unwrap();
+                let left = blocks.pop().unwrap();
+                blocks.push(join(left, label(node), right));
+            },
+        }
+    }
+
+    blocks.pop().unwrap()
+}
+
+/// A step of [`layout`]'s walk.
+enum LayoutWork<'a, N> {
+    Visit(Option<&'a N>),
+    /// Both children are laid out and on top of the block stack.
+    Join(&'a N),
+}
+
+fn label<N: BinaryNode>(node: &N) -> String
+where
+    N::Value: Display,
+{
+    let label = node.value().to_string();
+    match label.is_empty() {
+        true => " ".to_string(),
+        false => label,
+    }
+}
+
+/// Draws `label` above the two subtrees, with a branch down to each.
+fn join(mut left: Block, label: String, mut right: Block) -> Block {
+    let label_width = label.chars().count();
+
+    let mut lines = vec![
+        format!(
+            "{}{}{}{}{}",
+            " ".repeat(left.middle + 1),
+            "_".repeat(left.width - left.middle - 1),
+            label,
+            "_".repeat(right.middle),
+            " ".repeat(right.width - right.middle),
+        ),
+        format!(
+            "{}/{}\\{}",
+            " ".repeat(left.middle),
+            " ".repeat(
+                left.width - left.middle - 1 + label_width + right.middle
+            ),
+            " ".repeat(right.width - right.middle - 1),
+        ),
+    ];
+
+    let rows = left.lines.len().max(right.lines.len());
+    left.lines.resize(rows, " ".repeat(left.width));
+    right.lines.resize(rows, " ".repeat(right.width));
+    let gap = " ".repeat(label_width);
+    for (left, right) in left.lines.iter().zip(&right.lines) {
+        lines.push(format!("{left}{gap}{right}"));
+    }
+
+    Block {
+        lines,
+        width: left.width + label_width + right.width,
+        middle: left.width + label_width / 2,
+    }
+}
+
+fn leaf(label: String) -> Block {
+    let width = label.chars().count();
+    Block {
+        lines: vec![label],
+        width,
+        middle: width / 2,
+    }
+}
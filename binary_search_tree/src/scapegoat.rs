@@ -0,0 +1,403 @@
+// Copyright (C) 2025 BastIsAwesome (bastisawesomeltd@gmail.com)
+//
+// This file is part of deque.
+//
+// deque is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// deque is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with deque.  If not, see <https://www.gnu.org/licenses/>.
+use std::fmt::Display;
+
+use crate::bst::{self, BstNode};
+use crate::render;
+use crate::trace::{NoObserver, TreeObserver};
+use crate::validate::{
+    self, Direction, InvariantViolation, TreeStats, ViolationKind,
+};
+
+type Link<T> = Option<Box<BstNode<T>>>;
+
+/// Balance used by [`ScapegoatTree::new`].
+pub const DEFAULT_ALPHA: f64 = 2.0 / 3.0;
+
+/// Balanced binary search tree that keeps no balance data in its nodes.
+///
+/// Only the tree's size and the largest size since the last full rebuild are
+/// tracked. An insert that lands deeper than `log(size)` in base `1 / alpha`
+/// finds the first ancestor whose child holds more than `alpha` of its nodes,
+/// the scapegoat, and rebuilds that subtree into perfect balance. Deletes
+/// rebuild the whole tree once it has shrunk below `alpha` of its largest
+/// size.
+///
+/// Insertions and deletions report their steps to the tree's
+/// [`TreeObserver`]. Rebuilds replace whole subtrees instead of rotating, so
+/// no rotations are reported.
+pub struct ScapegoatTree<T: PartialEq + PartialOrd, O = NoObserver> {
+    root: Link<T>,
+    len: usize,
+    max_len: usize,
+    alpha: f64,
+    observer: O,
+}
+
+impl<T: PartialEq + PartialOrd> ScapegoatTree<T> {
+    pub fn new(data: Option<T>) -> Self {
+        let mut tree = Self::with_alpha(DEFAULT_ALPHA);
+        if let Some(data) = data {
+            tree.insert(data);
+        }
+        tree
+    }
+
+    /// Smaller values of `alpha` keep the tree closer to perfect balance at
+    /// the cost of more frequent rebuilds.
+    ///
+    /// Panics unless `0.5 < alpha < 1.0`.
+    pub fn with_alpha(alpha: f64) -> Self {
+        Self::with_alpha_and_observer(alpha, NoObserver)
+    }
+}
+
+impl<T: PartialEq + PartialOrd, O: TreeObserver<T>> ScapegoatTree<T, O> {
+    pub fn with_observer(observer: O) -> Self {
+        Self::with_alpha_and_observer(DEFAULT_ALPHA, observer)
+    }
+
+    /// Panics unless `0.5 < alpha < 1.0`.
+    pub fn with_alpha_and_observer(alpha: f64, observer: O) -> Self {
+        assert!(alpha > 0.5 && alpha < 1.0, "alpha must be in (0.5, 1.0)");
+
+        Self {
+            root: None,
+            len: 0,
+            max_len: 0,
+            alpha,
+            observer,
+        }
+    }
+
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    pub fn insert(&mut self, value: T) {
+        let limit = depth_limit(self.len + 1, self.alpha);
+        let observer = &mut self.observer;
+        match insert(&mut self.root, value, 0, limit, self.alpha, observer) {
+            Insert::Exists => return,
+            Insert::Done => {},
+            // No ancestor was unbalanced enough by the numbers, which only
+            // rounding can cause. Rebuilding everything restores the bound.
+            Insert::TooDeep(_) => rebuild(&mut self.root),
+        }
+
+        self.len += 1;
+        self.max_len = self.max_len.max(self.len);
+    }
+
+    pub fn delete(&mut self, value: T) {
+        if !bst::delete(&mut self.root, value, &mut self.observer) {
+            return;
+        }
+
+        self.len -= 1;
+        if (self.len as f64) < self.alpha * self.max_len as f64 {
+            rebuild(&mut self.root);
+            self.max_len = self.len;
+        }
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        match &self.root {
+            Some(root) => bst::contains(root, value),
+            None => false,
+        }
+    }
+
+    pub fn min(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        bst::min(self.root.as_ref()?)
+    }
+
+    pub fn max(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        bst::max(self.root.as_ref()?)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn size(&self) -> usize {
+        self.len
+    }
+
+    pub fn height(&self) -> usize {
+        validate::height(self.root.as_deref())
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+        self.max_len = 0;
+    }
+
+    /// Checks the search tree order, the stored size, and that no node sits
+    /// deeper than the largest size since the last rebuild allows.
+    pub fn validate(&self) -> Result<TreeStats, InvariantViolation> {
+        let stats = match &self.root {
+            Some(root) => validate::check_order(root.as_ref())?,
+            None => TreeStats::default(),
+        };
+
+        validate::check_size(&[], self.len, stats.size)?;
+
+        let limit = depth_limit(self.max_len, self.alpha) + 1;
+        if stats.height > limit {
+            return Err(InvariantViolation::new(
+                Vec::new(),
+                ViolationKind::TooTall {
+                    height: stats.height,
+                    limit,
+                },
+            ));
+        }
+
+        Ok(stats)
+    }
+}
+
+impl<T: PartialEq + PartialOrd + Display, O> ScapegoatTree<T, O> {
+    pub fn to_dot(&self) -> String {
+        render::to_dot("ScapegoatTree", self.root.as_deref())
+    }
+
+    pub fn render_ascii(&self) -> String {
+        render::render_ascii(self.root.as_deref())
+    }
+}
+
+/// Deepest a node may sit in a tree of `len` nodes, the root being at depth
+/// zero: `floor(log(len))` in base `1 / alpha`.
+fn depth_limit(len: usize, alpha: f64) -> usize {
+    if len <= 1 {
+        return 0;
+    }
+
+    ((len as f64).ln() / (1.0 / alpha).ln()).floor() as usize
+}
+
+enum Insert {
+    /// The value was already in the tree.
+    Exists,
+    /// The value was added and the tree is within its depth limit.
+    Done,
+    /// The value was added too deep and no scapegoat has been found yet.
+    /// Holds the size of the subtree just returned from.
+    TooDeep(usize),
+}
+
+fn insert<T: PartialEq + PartialOrd, O: TreeObserver<T>>(
+    link: &mut Link<T>,
+    value: T,
+    depth: usize,
+    limit: usize,
+    alpha: f64,
+    observer: &mut O,
+) -> Insert {
+    let Some(node) = link else {
+        observer.on_link(&value);
+        *link = Some(Box::new(BstNode::new(value)));
+        return match depth > limit {
+            true => Insert::TooDeep(1),
+            false => Insert::Done,
+        };
+    };
+
+    observer.on_compare(&value, &node.data);
+    if node.data == value {
+        return Insert::Exists;
+    }
+
+    let (child, sibling) = if value < node.data {
+        observer.on_descend(Direction::Left);
+        (&mut node.left, &node.right)
+    } else {
+        observer.on_descend(Direction::Right);
+        (&mut node.right, &node.left)
+    };
+
+    let depth = depth + 1;
+    let child_size = match insert(child, value, depth, limit, alpha, observer) {
+        Insert::TooDeep(child_size) => child_size,
+        done => return done,
+    };
+
+    let sibling_size = sibling.as_deref().map_or(0, bst::size);
+    let size = 1 + child_size + sibling_size;
+    if child_size as f64 > alpha * size as f64 {
+        rebuild(link);
+        return Insert::Done;
+    }
+
+    Insert::TooDeep(size)
+}
+
+/// Rebuilds the subtree into perfect balance, reusing its nodes.
+fn rebuild<T: PartialEq + PartialOrd>(link: &mut Link<T>) {
+    let Some(root) = link.take() else {
+        return;
+    };
+
+    let nodes = flatten(root);
+    let len = nodes.len();
+    *link = build(&mut nodes.into_iter(), len);
+}
+
+/// Detaches every node of the subtree, returning them in order.
+fn flatten<T: PartialEq + PartialOrd>(
+    root: Box<BstNode<T>>,
+) -> Vec<Box<BstNode<T>>> {
+    let mut nodes = vec![];
+    let mut stack: Vec<Box<BstNode<T>>> = vec![];
+    let mut current = Some(root);
+    loop {
+        while let Some(mut node) = current {
+            current = node.left.take();
+            stack.push(node);
+        }
+
+        let Some(mut node) = stack.pop() else {
+            break;
+        };
+        current = node.right.take();
+        nodes.push(node);
+    }
+
+    nodes
+}
+
+/// Builds a perfectly balanced subtree from the next `len` nodes, which must
+/// arrive in order.
+fn build<T: PartialEq + PartialOrd>(
+    nodes: &mut impl Iterator<Item = Box<BstNode<T>>>,
+    len: usize,
+) -> Link<T> {
+    if len == 0 {
+        return None;
+    }
+
+    let left = build(nodes, len / 2);
+    let mut node = nodes.next().unwrap();
+    node.left = left;
+    node.right = build(nodes, len - len / 2 - 1);
+    Some(node)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::conformance::{conformance_tests, empty, filled, impl_test_set};
+
+    impl_test_set!(ScapegoatTree, |_| ScapegoatTree::new(None), delete);
+    conformance_tests!(ScapegoatTree<usize>);
+
+    #[test]
+    fn test_insert() {
+        let mut tree: ScapegoatTree<usize> = filled();
+        tree.insert(25);
+        tree.insert(17);
+        assert_eq!(tree.size(), 4);
+        assert!(tree.contains(17));
+        assert_eq!(tree.validate().unwrap(), TreeStats { size: 4, height: 3 });
+    }
+
+    #[test]
+    fn test_sorted_input_is_rebuilt() {
+        let mut tree: ScapegoatTree<usize> = empty();
+        for value in 0..10_000 {
+            tree.insert(value);
+        }
+        assert!(tree.height() <= depth_limit(10_000, tree.alpha()) + 1);
+        assert_eq!(tree.validate().unwrap().size, 10_000);
+    }
+
+    #[test]
+    fn test_alpha() {
+        // A tighter alpha gives a shallower tree for the same input.
+        let mut loose = ScapegoatTree::with_alpha(0.9);
+        let mut tight = ScapegoatTree::with_alpha(0.55);
+        for value in 0..1000 {
+            loose.insert(value);
+            tight.insert(value);
+        }
+        assert!(tight.height() < loose.height());
+        assert!(loose.validate().is_ok());
+        assert!(tight.validate().is_ok());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_alpha_out_of_range() {
+        ScapegoatTree::<usize>::with_alpha(0.5);
+    }
+
+    #[test]
+    fn test_delete_rebuilds() {
+        let mut tree: ScapegoatTree<usize> = empty();
+        for value in 0..64 {
+            tree.insert(value);
+        }
+
+        // Removing the left half leaves a lopsided tree until the size drops
+        // below alpha of its peak and everything is rebuilt.
+        for value in 0..21 {
+            tree.delete(value);
+        }
+        assert_eq!(tree.max_len, 64);
+        tree.delete(21);
+        assert_eq!(tree.max_len, 42);
+        assert_eq!(tree.height(), 6);
+        assert_eq!(tree.min(), Some(22));
+        assert!(tree.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_too_tall() {
+        let mut tree: ScapegoatTree<usize> = empty();
+        for value in 0..8 {
+            tree.insert(value);
+        }
+        let mut node = tree.root.as_mut().unwrap();
+        while node.right.is_some() {
+            node = node.right.as_mut().unwrap();
+        }
+        for value in 8..16 {
+            node.right = Some(Box::new(BstNode::new(value)));
+            node = node.right.as_mut().unwrap();
+        }
+        tree.len = 16;
+        let violation = tree.validate().unwrap_err();
+        assert!(matches!(violation.kind, ViolationKind::TooTall { .. }));
+    }
+}
@@ -0,0 +1,654 @@
+// Copyright (C) 2025 BastIsAwesome (bastisawesomeltd@gmail.com)
+//
+// This file is part of deque.
+//
+// deque is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// deque is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with deque.  If not, see <https://www.gnu.org/licenses/>.
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+
+use crate::rng::Rng;
+
+/// Most levels a node can have. Enough for far more entries than fit in
+/// memory when each level holds about half the one below.
+pub const MAX_LEVEL: usize = 32;
+
+type NodeId = usize;
+
+/// Slot of the head node, which holds no entry and has a link on every
+/// level in use.
+const HEAD: NodeId = 0;
+
+/// Picks how many levels each new node gets: one, plus one more for every
+/// coin flip that comes up heads, up to [`MAX_LEVEL`].
+///
+/// The choice never depends on the list itself, so a concurrent list can
+/// draw levels before taking any locks.
+#[derive(Debug, Clone)]
+pub(crate) struct LevelGenerator {
+    rng: Rng,
+}
+
+impl LevelGenerator {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+        }
+    }
+
+    pub(crate) fn from_entropy() -> Self {
+        Self {
+            rng: Rng::from_entropy(),
+        }
+    }
+
+    pub(crate) fn next_level(&mut self) -> usize {
+        level_from_bits(self.rng.next_u64())
+    }
+}
+
+/// Turns random bits into a level, where each level is half as likely as
+/// the one below it.
+pub(crate) fn level_from_bits(bits: u64) -> usize {
+    // Each trailing zero bit is one fair coin flip.
+    let flips = bits.trailing_zeros() as usize;
+    (flips + 1).min(MAX_LEVEL)
+}
+
+/// A forward pointer on one level.
+#[derive(Debug, Clone, Copy)]
+struct Link {
+    next: Option<NodeId>,
+    /// How many entries the link skips over, counting the one it lands on.
+    /// A link to the end lands one past the last entry.
+    width: usize,
+}
+
+struct SkipNode<K, V> {
+    /// `None` only for the head and for free slots.
+    entry: Option<(K, V)>,
+    /// One link per level the node takes part in, lowest first.
+    links: Vec<Link>,
+}
+
+/// The nodes just before a key on every level, found on the way down.
+struct Path {
+    preds: Vec<NodeId>,
+    /// Position of each predecessor, the head being at zero and the first
+    /// entry at one.
+    ranks: Vec<usize>,
+}
+
+/// Ordered map stored as a skip list: a sorted linked list with extra
+/// express lanes, each holding about half the nodes of the lane below.
+/// Searches start on the highest lane and drop down a level whenever the
+/// next step would overshoot, which takes expected O(log n) steps.
+///
+/// Each link also records how many entries it skips, so the rank of a key
+/// comes out of the same walk.
+///
+/// Nodes live in an arena and refer to each other by index. A node's levels
+/// are fixed when it is created and every update goes through the
+/// predecessors found by a single search, which is the shape lock-free and
+/// fine-grained locking skip lists are built on.
+pub struct SkipListMap<K: PartialEq + PartialOrd, V> {
+    nodes: Vec<SkipNode<K, V>>,
+    /// Arena slots that can be reused.
+    free: Vec<NodeId>,
+    len: usize,
+    levels: LevelGenerator,
+}
+
+impl<K: PartialEq + PartialOrd, V> SkipListMap<K, V> {
+    /// Seeds the level generator from per-process randomness.
+    pub fn new() -> Self {
+        Self::with_levels(LevelGenerator::from_entropy())
+    }
+
+    /// Two lists built with the same seed and the same operations have the
+    /// same shape.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_levels(LevelGenerator::new(seed))
+    }
+
+    fn with_levels(levels: LevelGenerator) -> Self {
+        Self {
+            nodes: vec![SkipNode {
+                entry: None,
+                links: vec![Link {
+                    next: None,
+                    width: 1,
+                }],
+            }],
+            free: vec![],
+            len: 0,
+            levels,
+        }
+    }
+
+    /// Adds the entry, returning the value it replaced if the key was
+    /// already present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let path = self.find(&key);
+        if let Some(next) = self.next_at(path.preds[0], 0) {
+            if let Some((found, old)) = &mut self.nodes[next].entry {
+                if *found == key {
+                    return Some(mem::replace(old, value));
+                }
+            }
+        }
+
+        let level = self.levels.next_level();
+        let mut path = path;
+        // New lanes start at the head and run to the end of the list.
+        while self.nodes[HEAD].links.len() < level {
+            self.nodes[HEAD].links.push(Link {
+                next: None,
+                width: self.len + 1,
+            });
+            path.preds.push(HEAD);
+            path.ranks.push(0);
+        }
+
+        let rank = path.ranks[0] + 1;
+        let mut links = Vec::with_capacity(level);
+        for (i, (&pred, &pred_rank)) in
+            path.preds.iter().zip(&path.ranks).enumerate()
+        {
+            let link = &mut self.nodes[pred].links[i];
+            if i < level {
+                // The entry the old link landed on moves up by one.
+                let end = pred_rank + link.width + 1;
+                links.push(Link {
+                    next: link.next,
+                    width: end - rank,
+                });
+                link.width = rank - pred_rank;
+            } else {
+                link.width += 1;
+            }
+        }
+
+        let node = self.alloc(SkipNode {
+            entry: Some((key, value)),
+            links,
+        });
+        for (i, &pred) in path.preds.iter().take(level).enumerate() {
+            self.nodes[pred].links[i].next = Some(node);
+        }
+        self.len += 1;
+        None
+    }
+
+    /// Takes the entry out, returning its value.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let path = self.find(key);
+        let node = self.next_at(path.preds[0], 0)?;
+        if self.key_of(node) != key {
+            return None;
+        }
+
+        let links = mem::take(&mut self.nodes[node].links);
+        for (i, &pred) in path.preds.iter().enumerate() {
+            let link = &mut self.nodes[pred].links[i];
+            match links.get(i) {
+                Some(removed) => {
+                    link.next = removed.next;
+                    link.width += removed.width - 1;
+                },
+                None => link.width -= 1,
+            }
+        }
+
+        // Drop lanes that no longer hold anything, keeping the bottom one.
+        let head = &mut self.nodes[HEAD].links;
+        while head.len() > 1 && head.last().unwrap().next.is_none() {
+            head.pop();
+        }
+
+        self.len -= 1;
+        self.release(node).map(|(_, value)| value)
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let node = self.lower_bound(key)?;
+        match &self.nodes[node].entry {
+            Some((found, value)) if found == key => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let node = self.lower_bound(key)?;
+        match &mut self.nodes[node].entry {
+            Some((found, value)) if found == key => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Entry with the smallest key.
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let node = self.next_at(HEAD, 0)?;
+        self.entry_of(node)
+    }
+
+    /// Entry with the largest key.
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let mut node = HEAD;
+        for level in (0..self.nodes[HEAD].links.len()).rev() {
+            while let Some(next) = self.next_at(node, level) {
+                node = next;
+            }
+        }
+        self.entry_of(node)
+    }
+
+    /// Number of keys less than `key`, whether or not `key` is present.
+    pub fn rank(&self, key: &K) -> usize {
+        self.find(key).ranks[0]
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes every entry but keeps drawing levels from the same generator.
+    pub fn clear(&mut self) {
+        self.nodes.truncate(1);
+        self.nodes[HEAD].links = vec![Link {
+            next: None,
+            width: 1,
+        }];
+        self.free.clear();
+        self.len = 0;
+    }
+
+    pub fn iter(&self) -> Range<'_, K, V> {
+        Range {
+            list: self,
+            next: self.next_at(HEAD, 0),
+            end: Bound::Unbounded,
+        }
+    }
+
+    /// Entries with keys within `range`, in order. Only the start is
+    /// searched for; the walk then follows the bottom level.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, V>
+    where
+        K: Clone,
+    {
+        let next = match range.start_bound() {
+            Bound::Unbounded => self.next_at(HEAD, 0),
+            Bound::Included(start) => self.lower_bound(start),
+            Bound::Excluded(start) => {
+                let node = self.lower_bound(start);
+                match node.filter(|&node| self.key_of(node) == start) {
+                    Some(node) => self.next_at(node, 0),
+                    None => node,
+                }
+            },
+        };
+
+        Range {
+            list: self,
+            next,
+            end: range.end_bound().cloned(),
+        }
+    }
+
+    /// Walks down from the top lane to just before `key` on every level.
+    fn find(&self, key: &K) -> Path {
+        let height = self.nodes[HEAD].links.len();
+        let mut path = Path {
+            preds: vec![HEAD; height],
+            ranks: vec![0; height],
+        };
+
+        let mut node = HEAD;
+        let mut rank = 0;
+        for level in (0..height).rev() {
+            loop {
+                let link = self.nodes[node].links[level];
+                match link.next {
+                    Some(next) if self.key_of(next) < key => {
+                        node = next;
+                        rank += link.width;
+                    },
+                    _ => break,
+                }
+            }
+            path.preds[level] = node;
+            path.ranks[level] = rank;
+        }
+
+        path
+    }
+
+    /// First node whose key is not less than `key`.
+    fn lower_bound(&self, key: &K) -> Option<NodeId> {
+        let path = self.find(key);
+        self.next_at(path.preds[0], 0)
+    }
+
+    fn next_at(&self, node: NodeId, level: usize) -> Option<NodeId> {
+        self.nodes[node].links[level].next
+    }
+
+    fn key_of(&self, node: NodeId) -> &K {
+        &self.nodes[node].entry.as_ref().expect("head has no key").0
+    }
+
+    fn entry_of(&self, node: NodeId) -> Option<(&K, &V)> {
+        let (key, value) = self.nodes[node].entry.as_ref()?;
+        Some((key, value))
+    }
+
+    fn alloc(&mut self, node: SkipNode<K, V>) -> NodeId {
+        match self.free.pop() {
+            Some(id) => {
+                self.nodes[id] = node;
+                id
+            },
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            },
+        }
+    }
+
+    fn release(&mut self, id: NodeId) -> Option<(K, V)> {
+        self.free.push(id);
+        self.nodes[id].links = vec![];
+        self.nodes[id].entry.take()
+    }
+}
+
+impl<K: PartialEq + PartialOrd, V> Default for SkipListMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Entries of a [`SkipListMap`] in order, walking the bottom level.
+pub struct Range<'a, K: PartialEq + PartialOrd, V> {
+    list: &'a SkipListMap<K, V>,
+    next: Option<NodeId>,
+    end: Bound<K>,
+}
+
+impl<'a, K: PartialEq + PartialOrd, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next?;
+        let (key, value) = self.list.entry_of(node)?;
+        let in_range = match &self.end {
+            Bound::Included(end) => key <= end,
+            Bound::Excluded(end) => key < end,
+            Bound::Unbounded => true,
+        };
+        if !in_range {
+            self.next = None;
+            return None;
+        }
+
+        self.next = self.list.next_at(node, 0);
+        Some((key, value))
+    }
+}
+
+/// Ordered set stored as a [`SkipListMap`] with no values.
+pub struct SkipList<T: PartialEq + PartialOrd> {
+    map: SkipListMap<T, ()>,
+}
+
+impl<T: PartialEq + PartialOrd> SkipList<T> {
+    /// Seeds the level generator from per-process randomness.
+    pub fn new(data: Option<T>) -> Self {
+        let mut list = Self {
+            map: SkipListMap::new(),
+        };
+        if let Some(data) = data {
+            list.insert(data);
+        }
+        list
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            map: SkipListMap::with_seed(seed),
+        }
+    }
+
+    pub fn insert(&mut self, value: T) {
+        self.map.insert(value, ());
+    }
+
+    pub fn remove(&mut self, value: T) {
+        self.map.remove(&value);
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.map.contains_key(&value)
+    }
+
+    pub fn min(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.map.first().map(|(value, _)| value.clone())
+    }
+
+    pub fn max(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.map.last().map(|(value, _)| value.clone())
+    }
+
+    /// Number of values less than `value`, whether or not it is present.
+    pub fn rank(&self, value: T) -> usize {
+        self.map.rank(&value)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn size(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    pub fn iter(&self) -> SetRange<'_, T> {
+        SetRange {
+            inner: self.map.iter(),
+        }
+    }
+
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> SetRange<'_, T>
+    where
+        T: Clone,
+    {
+        SetRange {
+            inner: self.map.range(range),
+        }
+    }
+}
+
+/// Values of a [`SkipList`] in order.
+pub struct SetRange<'a, T: PartialEq + PartialOrd> {
+    inner: Range<'a, T, ()>,
+}
+
+impl<'a, T: PartialEq + PartialOrd> Iterator for SetRange<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(value, _)| value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::conformance::{
+        self, conformance_tests, empty, from_values, impl_test_set,
+    };
+    use std::collections::BTreeMap;
+
+    impl_test_set!(
+        SkipList,
+        |seed| SkipList::with_seed(seed),
+        remove,
+        |list| list.iter().is_sorted_by(|a, b| a < b)
+            && list.iter().count() == list.size()
+    );
+    conformance_tests!(SkipList<usize>);
+
+    fn create_filled() -> SkipList<usize> {
+        from_values([50, 25, 100, 75, 10])
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut list = create_filled();
+        list.insert(25);
+        list.insert(17);
+        assert_eq!(list.size(), 6);
+        assert!(list.contains(17));
+        assert!(!list.contains(18));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut list = create_filled();
+        list.remove(50);
+        list.remove(255);
+        assert_eq!(list.size(), 4);
+        assert!(!list.contains(50));
+
+        for value in [10, 25, 75, 100] {
+            list.remove(value);
+        }
+        assert!(list.is_empty());
+        assert_eq!(list.min(), None);
+        assert_eq!(list.map.nodes[HEAD].links.len(), 1);
+    }
+
+    #[test]
+    fn test_rank() {
+        let list = create_filled();
+        assert_eq!(list.rank(10), 0);
+        assert_eq!(list.rank(50), 2);
+        assert_eq!(list.rank(51), 3);
+        assert_eq!(list.rank(1000), 5);
+    }
+
+    #[test]
+    fn test_range() {
+        let list = create_filled();
+        let values: Vec<_> = list.range(25..=75).copied().collect();
+        assert_eq!(values, [25, 50, 75]);
+
+        let values: Vec<_> = list
+            .range((Bound::Excluded(25), Bound::Excluded(100)))
+            .copied()
+            .collect();
+        assert_eq!(values, [50, 75]);
+
+        let values: Vec<_> = list.iter().copied().collect();
+        assert_eq!(values, [10, 25, 50, 75, 100]);
+        assert_eq!(list.range(101..).next(), None);
+    }
+
+    #[test]
+    fn test_same_seed_same_shape() {
+        let mut a: SkipList<usize> = empty();
+        let mut b: SkipList<usize> = empty();
+        for value in 0..100 {
+            a.insert(value);
+            b.insert(value);
+        }
+        let heights = |list: &SkipList<usize>| {
+            list.map
+                .nodes
+                .iter()
+                .map(|node| node.links.len())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(heights(&a), heights(&b));
+    }
+
+    #[test]
+    fn test_map() {
+        let mut map = SkipListMap::with_seed(1);
+        assert_eq!(map.insert("b", 2), None);
+        assert_eq!(map.insert("a", 1), None);
+        assert_eq!(map.insert("b", 3), Some(2));
+        *map.get_mut(&"a").unwrap() += 10;
+
+        assert_eq!(map.get(&"a"), Some(&11));
+        assert_eq!(map.get(&"c"), None);
+        assert_eq!(map.first(), Some((&"a", &11)));
+        assert_eq!(map.last(), Some((&"b", &3)));
+        assert_eq!(map.remove(&"b"), Some(3));
+        assert_eq!(map.remove(&"b"), None);
+        assert_eq!(map.len(), 1);
+
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.iter().next(), None);
+    }
+
+    #[test]
+    fn test_rank_and_range_match_btree_set() {
+        for seed in 1..=64 {
+            let (list, set) = conformance::random_ops::<SkipList<usize>>(seed);
+            for value in 0..64 {
+                assert_eq!(list.rank(value), set.range(..value).count());
+            }
+            assert!(list.iter().eq(set.iter()), "seed {seed}");
+            assert!(list.range(20..40).eq(set.range(20..40)), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn test_map_matches_btree_map() {
+        let mut rng = Rng::new(99);
+        let mut map = SkipListMap::with_seed(99);
+        let mut expected = BTreeMap::new();
+
+        for step in 0..2000 {
+            let key = rng.next_u64() % 256;
+            if rng.next_u64().is_multiple_of(3) {
+                assert_eq!(map.remove(&key), expected.remove(&key));
+            } else {
+                assert_eq!(map.insert(key, step), expected.insert(key, step));
+            }
+        }
+
+        assert!(map.iter().eq(expected.iter()));
+    }
+}
@@ -0,0 +1,362 @@
+// Copyright (C) 2025 BastIsAwesome (bastisawesomeltd@gmail.com)
+//
+// This file is part of deque.
+//
+// deque is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// deque is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with deque.  If not, see <https://www.gnu.org/licenses/>.
+use std::fmt::Display;
+
+use crate::bst::{self, BstNode};
+use crate::render;
+use crate::trace::{NoObserver, TreeObserver};
+use crate::validate::{self, Direction, InvariantViolation, TreeStats};
+
+/// Self-adjusting binary search tree. Every insert, delete and `contains`
+/// moves the node it touched to the root, so recently used values stay cheap
+/// to reach.
+pub struct SplayTree<T: PartialEq + PartialOrd, O = NoObserver> {
+    root: Option<Box<BstNode<T>>>,
+    len: usize,
+    observer: O,
+}
+
+impl<T: PartialEq + PartialOrd> SplayTree<T> {
+    pub fn new(data: Option<T>) -> Self {
+        let mut tree = Self::with_observer(NoObserver);
+        if let Some(data) = data {
+            tree.insert(data);
+        }
+        tree
+    }
+}
+
+impl<T: PartialEq + PartialOrd, O: TreeObserver<T>> SplayTree<T, O> {
+    pub fn with_observer(observer: O) -> Self {
+        Self {
+            root: None,
+            len: 0,
+            observer,
+        }
+    }
+
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    pub fn insert(&mut self, value: T) {
+        let Some(root) = self.root.take() else {
+            self.observer.on_link(&value);
+            self.root = Some(Box::new(BstNode::new(value)));
+            self.len = 1;
+            return;
+        };
+
+        let mut root = splay(root, &value, &mut self.observer);
+        if root.data == value {
+            self.root = Some(root);
+            return;
+        }
+
+        // The old root is the new value's neighbour, so it and one of its
+        // subtrees hang off the new node.
+        self.observer.on_link(&value);
+        let mut node = Box::new(BstNode::new(value));
+        if node.data < root.data {
+            node.left = root.left.take();
+            node.right = Some(root);
+        } else {
+            node.right = root.right.take();
+            node.left = Some(root);
+        }
+        self.root = Some(node);
+        self.len += 1;
+    }
+
+    pub fn delete(&mut self, value: T) {
+        let Some(root) = self.root.take() else {
+            return;
+        };
+
+        let mut root = splay(root, &value, &mut self.observer);
+        if root.data != value {
+            self.root = Some(root);
+            return;
+        }
+
+        self.observer.on_unlink(&root.data);
+        self.root = match root.left.take() {
+            Some(left) => {
+                // Everything on the left is smaller than `value`, so splaying
+                // for it raises the largest node, which has no right child.
+                let mut left = splay(left, &value, &mut self.observer);
+                left.right = root.right.take();
+                Some(left)
+            },
+            None => root.right.take(),
+        };
+        self.len -= 1;
+    }
+
+    /// Splays the value, or its nearest neighbour, to the root.
+    pub fn contains(&mut self, value: T) -> bool {
+        let Some(root) = self.root.take() else {
+            return false;
+        };
+
+        let root = splay(root, &value, &mut self.observer);
+        let found = root.data == value;
+        self.root = Some(root);
+        found
+    }
+
+    /// Looks the value up without restructuring the tree.
+    pub fn peek_contains(&self, value: T) -> bool {
+        match &self.root {
+            Some(root) => bst::contains(root, value),
+            None => false,
+        }
+    }
+
+    pub fn min(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        bst::min(self.root.as_ref()?)
+    }
+
+    pub fn max(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        bst::max(self.root.as_ref()?)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn size(&self) -> usize {
+        self.len
+    }
+
+    pub fn height(&self) -> usize {
+        validate::height(self.root.as_deref())
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+
+    /// Walks the whole tree checking that values are strictly ordered and
+    /// that the stored element count matches the nodes actually reachable.
+    pub fn validate(&self) -> Result<TreeStats, InvariantViolation> {
+        let stats = match &self.root {
+            Some(root) => validate::check_order(root.as_ref())?,
+            None => TreeStats::default(),
+        };
+
+        validate::check_size(&[], self.len, stats.size)?;
+
+        Ok(stats)
+    }
+}
+
+impl<T: PartialEq + PartialOrd + Display, O> SplayTree<T, O> {
+    pub fn to_dot(&self) -> String {
+        render::to_dot("SplayTree", self.root.as_deref())
+    }
+
+    pub fn render_ascii(&self) -> String {
+        render::render_ascii(self.root.as_deref())
+    }
+}
+
+/// Top-down splay. Brings the node holding `value`, or the last node visited
+/// while looking for it, to the root in a single pass down the tree.
+///
+/// Nodes passed on the way down are split off into the trees of values
+/// smaller and larger than `value`. They are kept on two stacks and hung back
+/// under the new root at the end, so nothing recurses.
+fn splay<T: PartialEq + PartialOrd, O: TreeObserver<T>>(
+    mut root: Box<BstNode<T>>,
+    value: &T,
+    observer: &mut O,
+) -> Box<BstNode<T>> {
+    // Linked through their right children once the descent is over.
+    let mut smaller: Vec<Box<BstNode<T>>> = vec![];
+    // Linked through their left children once the descent is over.
+    let mut larger: Vec<Box<BstNode<T>>> = vec![];
+
+    loop {
+        observer.on_compare(value, &root.data);
+        if *value < root.data {
+            let Some(mut left) = root.left.take() else {
+                break;
+            };
+
+            observer.on_compare(value, &left.data);
+            if *value < left.data {
+                // Zig-zig: rotate right before splitting the root off.
+                observer.on_rotate(Direction::Right, &root.data);
+                root.left = left.right.take();
+                left.right = Some(root);
+                root = left;
+                let Some(next) = root.left.take() else {
+                    break;
+                };
+                left = next;
+            }
+
+            observer.on_descend(Direction::Left);
+            larger.push(root);
+            root = left;
+        } else if *value > root.data {
+            let Some(mut right) = root.right.take() else {
+                break;
+            };
+
+            observer.on_compare(value, &right.data);
+            if *value > right.data {
+                // Zig-zig: rotate left before splitting the root off.
+                observer.on_rotate(Direction::Left, &root.data);
+                root.right = right.left.take();
+                right.left = Some(root);
+                root = right;
+                let Some(next) = root.right.take() else {
+                    break;
+                };
+                right = next;
+            }
+
+            observer.on_descend(Direction::Right);
+            smaller.push(root);
+            root = right;
+        } else {
+            break;
+        }
+    }
+
+    let mut left = root.left.take();
+    for mut node in smaller.into_iter().rev() {
+        node.right = left;
+        left = Some(node);
+    }
+    root.left = left;
+
+    let mut right = root.right.take();
+    for mut node in larger.into_iter().rev() {
+        node.left = right;
+        right = Some(node);
+    }
+    root.right = right;
+
+    root
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::conformance::{conformance_tests, empty, filled, impl_test_set};
+    use crate::trace::{TraceEvent, TraceRecorder};
+
+    impl_test_set!(SplayTree, |_| SplayTree::new(None), delete);
+    conformance_tests!(SplayTree<usize>);
+
+    fn root(tree: &SplayTree<usize>) -> usize {
+        tree.root.as_ref().unwrap().data
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut tree: SplayTree<usize> = empty();
+        tree.insert(16);
+        assert_eq!(root(&tree), 16);
+
+        tree.insert(8);
+        assert_eq!(root(&tree), 8);
+        assert_eq!(
+            tree.root.as_ref().unwrap().right.as_ref().unwrap().data,
+            16
+        );
+
+        tree.insert(8);
+        assert_eq!(tree.size(), 2);
+        assert!(tree.validate().is_ok());
+    }
+
+    #[test]
+    fn test_delete() {
+        let mut tree: SplayTree<usize> = filled();
+        tree.delete(50);
+        assert_eq!(root(&tree), 25);
+        assert!(!tree.peek_contains(50));
+        assert_eq!(tree.validate().unwrap(), TreeStats { size: 2, height: 2 });
+
+        tree.delete(255);
+        assert_eq!(tree.size(), 2);
+    }
+
+    #[test]
+    fn test_contains_splays() {
+        let mut tree: SplayTree<usize> = filled();
+        assert!(tree.contains(25));
+        assert_eq!(root(&tree), 25);
+
+        assert!(!tree.contains(75));
+        assert!(root(&tree) == 50 || root(&tree) == 100);
+        assert!(tree.validate().is_ok());
+    }
+
+    #[test]
+    fn test_peek_contains() {
+        let tree: SplayTree<usize> = filled();
+        assert!(tree.peek_contains(25));
+        assert!(!tree.peek_contains(75));
+        assert_eq!(root(&tree), 100);
+    }
+
+    #[test]
+    fn test_degenerate() {
+        // Inserting in order leaves a single left spine.
+        let mut tree: SplayTree<usize> = empty();
+        for value in 0..100_000 {
+            tree.insert(value);
+        }
+        assert_eq!(tree.height(), 100_000);
+
+        // Splaying the deepest node roughly halves the depth of the path.
+        assert!(tree.contains(0));
+        assert_eq!(root(&tree), 0);
+        assert!(tree.height() < 60_000);
+        assert_eq!(tree.validate().unwrap().size, 100_000);
+    }
+
+    #[test]
+    fn test_observer_rotate() {
+        let mut tree = SplayTree::with_observer(TraceRecorder::new());
+        for value in [3, 2, 1] {
+            tree.insert(value);
+        }
+        tree.observer_mut().clear();
+        tree.contains(3);
+        assert!(tree.observer().events().contains(&TraceEvent::Rotate {
+            direction: Direction::Left,
+            pivot: 1,
+        }));
+    }
+}
@@ -0,0 +1,237 @@
+// Copyright (C) 2025 BastIsAwesome (bastisawesomeltd@gmail.com)
+//
+// This file is part of deque.
+//
+// deque is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// deque is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with deque.  If not, see <https://www.gnu.org/licenses/>.
+use std::fmt::{Display, Write};
+
+use crate::validate::Direction;
+
+/// Callbacks for the individual steps a tree algorithm takes.
+///
+/// Every method defaults to doing nothing, so an observer only overrides the
+/// steps it cares about. Trees are generic over their observer, which means
+/// the default [`NoObserver`] compiles away entirely.
+pub trait TreeObserver<T> {
+    /// `value` was compared against the value stored in `node`.
+    fn on_compare(&mut self, _value: &T, _node: &T) {}
+
+    /// The algorithm moved down to the child on the given side.
+    fn on_descend(&mut self, _direction: Direction) {}
+
+    /// The subtree rooted at `pivot` was rotated in the given direction.
+    fn on_rotate(&mut self, _direction: Direction, _pivot: &T) {}
+
+    /// A new node holding `value` was linked into the tree.
+    fn on_link(&mut self, _value: &T) {}
+
+    /// The node holding `value` was spliced out of the tree.
+    fn on_unlink(&mut self, _value: &T) {}
+}
+
+/// Observer that ignores every event.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoObserver;
+
+impl<T> TreeObserver<T> for NoObserver {}
+
+/// A single recorded step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceEvent<T> {
+    Compare { value: T, node: T },
+    Descend { direction: Direction },
+    Rotate { direction: Direction, pivot: T },
+    Link { value: T },
+    Unlink { value: T },
+}
+
+/// Observer that records every event in order.
+#[derive(Debug, Clone)]
+pub struct TraceRecorder<T> {
+    events: Vec<TraceEvent<T>>,
+}
+
+impl<T> TraceRecorder<T> {
+    pub fn new() -> Self {
+        Self { events: vec![] }
+    }
+
+    pub fn events(&self) -> &[TraceEvent<T>] {
+        &self.events
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    /// Feeds the recorded events, in order, to another observer.
+    pub fn replay<O: TreeObserver<T>>(&self, observer: &mut O) {
+        for event in &self.events {
+            match event {
+                TraceEvent::Compare { value, node } => {
+                    observer.on_compare(value, node)
+                },
+                TraceEvent::Descend { direction } => {
+                    observer.on_descend(*direction)
+                },
+                TraceEvent::Rotate { direction, pivot } => {
+                    observer.on_rotate(*direction, pivot)
+                },
+                TraceEvent::Link { value } => observer.on_link(value),
+                TraceEvent::Unlink { value } => observer.on_unlink(value),
+            }
+        }
+    }
+}
+
+impl<T: Display> TraceRecorder<T> {
+    /// One JSON object per event, each on its own line. Values are written as
+    /// strings using their `Display` output.
+    pub fn to_json_lines(&self) -> String {
+        let mut out = String::new();
+        for event in &self.events {
+            match event {
+                TraceEvent::Compare { value, node } => writeln!(
+                    out,
+                    "{{\"event\":\"compare\",\"value\":{},\"node\":{}}}",
+                    json_string(value),
+                    json_string(node),
+                ),
+                TraceEvent::Descend { direction } => writeln!(
+                    out,
+                    "{{\"event\":\"descend\",\"direction\":\"{}\"}}",
+                    direction_name(*direction),
+                ),
+                TraceEvent::Rotate { direction, pivot } => writeln!(
+                    out,
+                    "{{\"event\":\"rotate\",\"direction\":\"{}\",\"pivot\":{}}}",
+                    direction_name(*direction),
+                    json_string(pivot),
+                ),
+                TraceEvent::Link { value } => writeln!(
+                    out,
+                    "{{\"event\":\"link\",\"value\":{}}}",
+                    json_string(value),
+                ),
+                TraceEvent::Unlink { value } => writeln!(
+                    out,
+                    "{{\"event\":\"unlink\",\"value\":{}}}",
+                    json_string(value),
+                ),
+            }
+            .unwrap();
+        }
+        out
+    }
+}
+
+impl<T> Default for TraceRecorder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> TreeObserver<T> for TraceRecorder<T> {
+    fn on_compare(&mut self, value: &T, node: &T) {
+        self.events.push(TraceEvent::Compare {
+            value: value.clone(),
+            node: node.clone(),
+        });
+    }
+
+    fn on_descend(&mut self, direction: Direction) {
+        self.events.push(TraceEvent::Descend { direction });
+    }
+
+    fn on_rotate(&mut self, direction: Direction, pivot: &T) {
+        self.events.push(TraceEvent::Rotate {
+            direction,
+            pivot: pivot.clone(),
+        });
+    }
+
+    fn on_link(&mut self, value: &T) {
+        self.events.push(TraceEvent::Link {
+            value: value.clone(),
+        });
+    }
+
+    fn on_unlink(&mut self, value: &T) {
+        self.events.push(TraceEvent::Unlink {
+            value: value.clone(),
+        });
+    }
+}
+
+fn direction_name(direction: Direction) -> String {
+    match direction {
+        Direction::Left => "left".to_string(),
+        Direction::Right => "right".to_string(),
+        Direction::Child(index) => index.to_string(),
+    }
+}
+
+fn json_string<T: Display>(value: &T) -> String {
+    let mut out = String::from("\"");
+    for c in value.to_string().chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                write!(out, "\\u{:04x}", c as u32).unwrap();
+            },
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_replay() {
+        let mut recorder = TraceRecorder::new();
+        recorder.on_compare(&1, &2);
+        recorder.on_descend(Direction::Left);
+        recorder.on_link(&1);
+
+        let mut copy = TraceRecorder::new();
+        recorder.replay(&mut copy);
+        assert_eq!(copy.events(), recorder.events());
+    }
+
+    #[test]
+    fn test_to_json_lines() {
+        let mut recorder = TraceRecorder::new();
+        recorder.on_compare(&"a\"b", &"c");
+        recorder.on_rotate(Direction::Right, &"d");
+        recorder.on_unlink(&"e\n");
+        assert_eq!(
+            recorder.to_json_lines(),
+            [
+                r#"{"event":"compare","value":"a\"b","node":"c"}"#,
+                r#"{"event":"rotate","direction":"right","pivot":"d"}"#,
+                r#"{"event":"unlink","value":"e\n"}"#,
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
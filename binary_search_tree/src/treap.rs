@@ -0,0 +1,488 @@
+// Copyright (C) 2025 BastIsAwesome (bastisawesomeltd@gmail.com)
+//
+// This file is part of deque.
+//
+// deque is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// deque is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with deque.  If not, see <https://www.gnu.org/licenses/>.
+use std::fmt::Display;
+
+use crate::node::BinaryNode;
+use crate::render;
+use crate::rng::Rng;
+use crate::trace::{NoObserver, TreeObserver};
+use crate::validate::{
+    self, Direction, InvariantViolation, TreeStats, ViolationKind,
+};
+
+type Link<T> = Option<Box<TreapNode<T>>>;
+
+/// Binary search tree on values and max-heap on random priorities. The
+/// random priorities keep the expected height logarithmic whatever order
+/// values arrive in, and make splitting and merging cheap.
+///
+/// Priorities come from a seedable generator: two treaps built with the same
+/// seed and the same operations have the same shape.
+///
+/// Insertions and removals report the search for the value and the node
+/// they link or unlink to the tree's [`TreeObserver`]. Restructuring is done
+/// by splitting and merging rather than rotating, so no rotations are
+/// reported.
+pub struct Treap<T: PartialEq + PartialOrd, O = NoObserver> {
+    root: Link<T>,
+    rng: Rng,
+    observer: O,
+}
+
+impl<T: PartialEq + PartialOrd> Treap<T> {
+    /// Seeds the priorities from per-process randomness.
+    pub fn new(data: Option<T>) -> Self {
+        let mut treap = Self::with_observer(NoObserver);
+        if let Some(data) = data {
+            treap.insert(data);
+        }
+        treap
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_seed_and_observer(seed, NoObserver)
+    }
+}
+
+impl<T: PartialEq + PartialOrd, O: TreeObserver<T>> Treap<T, O> {
+    /// Seeds the priorities from per-process randomness.
+    pub fn with_observer(observer: O) -> Self {
+        Self {
+            root: None,
+            rng: Rng::from_entropy(),
+            observer,
+        }
+    }
+
+    pub fn with_seed_and_observer(seed: u64, observer: O) -> Self {
+        Self {
+            root: None,
+            rng: Rng::new(seed),
+            observer,
+        }
+    }
+
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    pub fn insert(&mut self, value: T) {
+        if search(&self.root, &value, &mut self.observer) {
+            return;
+        }
+
+        self.observer.on_link(&value);
+        let node = Box::new(TreapNode::new(value, self.rng.next_u64()));
+        let (left, right) = split(self.root.take(), &node.data);
+        self.root = merge(merge(left, Some(node)), right);
+    }
+
+    pub fn remove(&mut self, value: T) {
+        remove(&mut self.root, &value, &mut self.observer);
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        search(&self.root, &value, &mut NoObserver)
+    }
+
+    /// Splits off every value greater than or equal to `key`. The returned
+    /// pair holds the values below `key` and the rest, in that order. The
+    /// lower half keeps the observer and the upper half gets a new one.
+    pub fn split(mut self, key: &T) -> (Self, Self)
+    where
+        O: Default,
+    {
+        let (left, right) = split(self.root.take(), key);
+        let right_rng = Rng::new(self.rng.next_u64());
+        (
+            Self {
+                root: left,
+                rng: self.rng,
+                observer: self.observer,
+            },
+            Self {
+                root: right,
+                rng: right_rng,
+                observer: O::default(),
+            },
+        )
+    }
+
+    /// Joins two treaps where every value in `left` is smaller than every
+    /// value in `right`. The result keeps drawing priorities from `left`'s
+    /// generator and keeps `left`'s observer.
+    ///
+    /// Panics if the ranges of the two treaps overlap.
+    pub fn merge(mut left: Self, mut right: Self) -> Self {
+        if let (Some(max), Some(min)) = (left.max_ref(), right.min_ref()) {
+            assert!(max < min, "merged treaps must not overlap");
+        }
+
+        left.root = merge(left.root.take(), right.root.take());
+        left
+    }
+
+    pub fn min(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.min_ref().cloned()
+    }
+
+    pub fn max(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.max_ref().cloned()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn size(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn height(&self) -> usize {
+        validate::height(self.root.as_deref())
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+    }
+
+    /// Checks the search tree order, that no child outranks its parent in
+    /// priority, and the subtree size stored on every node.
+    pub fn validate(&self) -> Result<TreeStats, InvariantViolation> {
+        let Some(root) = &self.root else {
+            return Ok(TreeStats::default());
+        };
+
+        let stats = validate::check_order(root.as_ref())?;
+        check_node(root, &mut Vec::new())?;
+        Ok(stats)
+    }
+
+    fn min_ref(&self) -> Option<&T> {
+        let mut node = self.root.as_deref()?;
+        while let Some(left) = &node.left {
+            node = left;
+        }
+        Some(&node.data)
+    }
+
+    fn max_ref(&self) -> Option<&T> {
+        let mut node = self.root.as_deref()?;
+        while let Some(right) = &node.right {
+            node = right;
+        }
+        Some(&node.data)
+    }
+}
+
+impl<T: PartialEq + PartialOrd + Display, O> Treap<T, O> {
+    pub fn to_dot(&self) -> String {
+        render::to_dot("Treap", self.root.as_deref())
+    }
+
+    pub fn render_ascii(&self) -> String {
+        render::render_ascii(self.root.as_deref())
+    }
+}
+
+fn size<T>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+/// Whether the subtree holds `value`, reporting each step of the search.
+fn search<T: PartialOrd, O: TreeObserver<T>>(
+    link: &Link<T>,
+    value: &T,
+    observer: &mut O,
+) -> bool {
+    let mut node = link.as_deref();
+    while let Some(current) = node {
+        observer.on_compare(value, &current.data);
+        if current.data == *value {
+            return true;
+        }
+
+        node = if *value < current.data {
+            observer.on_descend(Direction::Left);
+            current.left.as_deref()
+        } else {
+            observer.on_descend(Direction::Right);
+            current.right.as_deref()
+        };
+    }
+
+    false
+}
+
+/// Splits the subtree into the values below `key` and the rest.
+fn split<T: PartialOrd>(link: Link<T>, key: &T) -> (Link<T>, Link<T>) {
+    let Some(mut node) = link else {
+        return (None, None);
+    };
+
+    if node.data < *key {
+        let (left, right) = split(node.right.take(), key);
+        node.right = left;
+        node.update_size();
+        (Some(node), right)
+    } else {
+        let (left, right) = split(node.left.take(), key);
+        node.left = right;
+        node.update_size();
+        (left, Some(node))
+    }
+}
+
+/// Joins two subtrees where every value in `left` sorts before `right`. The
+/// root with the higher priority stays on top.
+fn merge<T>(left: Link<T>, right: Link<T>) -> Link<T> {
+    match (left, right) {
+        (None, right) => right,
+        (left, None) => left,
+        (Some(mut left), Some(mut right)) => {
+            if left.priority > right.priority {
+                left.right = merge(left.right.take(), Some(right));
+                left.update_size();
+                Some(left)
+            } else {
+                right.left = merge(Some(left), right.left.take());
+                right.update_size();
+                Some(right)
+            }
+        },
+    }
+}
+
+/// Returns whether the value was found and removed.
+fn remove<T: PartialOrd, O: TreeObserver<T>>(
+    link: &mut Link<T>,
+    value: &T,
+    observer: &mut O,
+) -> bool {
+    let Some(node) = link else {
+        // Value does not exist.
+        return false;
+    };
+
+    observer.on_compare(value, &node.data);
+    let removed = if *value < node.data {
+        observer.on_descend(Direction::Left);
+        remove(&mut node.left, value, observer)
+    } else if *value > node.data {
+        observer.on_descend(Direction::Right);
+        remove(&mut node.right, value, observer)
+    } else {
+        observer.on_unlink(&node.data);
+        let mut node = link.take().unwrap();
+        *link = merge(node.left.take(), node.right.take());
+        return true;
+    };
+
+    if removed {
+        node.size -= 1;
+    }
+    removed
+}
+
+/// Checks heap order and stored sizes below `node`, returning the number of
+/// nodes in the subtree.
+fn check_node<T>(
+    node: &TreapNode<T>,
+    path: &mut Vec<Direction>,
+) -> Result<usize, InvariantViolation> {
+    let mut size = 1;
+    for (direction, child) in [
+        (Direction::Left, &node.left),
+        (Direction::Right, &node.right),
+    ] {
+        let Some(child) = child else {
+            continue;
+        };
+
+        path.push(direction);
+        if child.priority > node.priority {
+            return Err(InvariantViolation::new(
+                path.clone(),
+                ViolationKind::HeapOrder,
+            ));
+        }
+        size += check_node(child, path)?;
+        path.pop();
+    }
+
+    validate::check_size(path, node.size, size)?;
+
+    Ok(size)
+}
+
+struct TreapNode<T> {
+    left: Link<T>,
+    right: Link<T>,
+    data: T,
+    priority: u64,
+    /// Number of nodes in the subtree rooted here, this one included.
+    size: usize,
+}
+
+impl<T> TreapNode<T> {
+    fn new(data: T, priority: u64) -> Self {
+        Self {
+            left: None,
+            right: None,
+            data,
+            priority,
+            size: 1,
+        }
+    }
+
+    fn update_size(&mut self) {
+        self.size = 1 + size(&self.left) + size(&self.right);
+    }
+}
+
+impl<T> BinaryNode for TreapNode<T> {
+    type Value = T;
+
+    fn value(&self) -> &T {
+        &self.data
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::conformance::{
+        conformance_tests, empty, filled_wide, impl_test_set,
+    };
+
+    impl_test_set!(Treap, |seed| Treap::with_seed(seed), remove);
+    conformance_tests!(Treap<usize>);
+
+    fn values(treap: &Treap<usize>) -> Vec<usize> {
+        fn walk(link: &Link<usize>, out: &mut Vec<usize>) {
+            if let Some(node) = link {
+                walk(&node.left, out);
+                out.push(node.data);
+                walk(&node.right, out);
+            }
+        }
+
+        let mut out = vec![];
+        walk(&treap.root, &mut out);
+        out
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut treap = filled_wide::<Treap<usize>>();
+        treap.insert(42);
+        assert_eq!(treap.size(), 7);
+        assert_eq!(values(&treap), [17, 25, 42, 50, 75, 100, 150]);
+        assert_eq!(treap.validate().unwrap().size, 7);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut treap = filled_wide::<Treap<usize>>();
+        treap.remove(50);
+        treap.remove(17);
+        treap.remove(255);
+        assert!(!treap.contains(50));
+        assert!(treap.contains(42));
+        assert_eq!(values(&treap), [25, 42, 75, 100, 150]);
+        assert!(treap.validate().is_ok());
+    }
+
+    #[test]
+    fn test_same_seed_same_shape() {
+        let a = filled_wide::<Treap<usize>>();
+        let b = filled_wide::<Treap<usize>>();
+        assert_eq!(a.to_dot(), b.to_dot());
+    }
+
+    #[test]
+    fn test_split() {
+        let (left, right) = filled_wide::<Treap<usize>>().split(&50);
+        assert_eq!(values(&left), [17, 25, 42]);
+        assert_eq!(values(&right), [50, 75, 100, 150]);
+        assert!(left.validate().is_ok());
+        assert!(right.validate().is_ok());
+
+        let (left, right) = filled_wide::<Treap<usize>>().split(&0);
+        assert!(left.is_empty());
+        assert_eq!(right.size(), 7);
+    }
+
+    #[test]
+    fn test_merge() {
+        let (left, right) = filled_wide::<Treap<usize>>().split(&60);
+        let treap = Treap::merge(left, right);
+        assert_eq!(values(&treap), [17, 25, 42, 50, 75, 100, 150]);
+        assert_eq!(treap.validate().unwrap().size, 7);
+
+        let treap = Treap::merge(treap, Treap::with_seed(1));
+        assert_eq!(treap.size(), 7);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_merge_overlapping() {
+        let left = filled_wide::<Treap<usize>>();
+        let mut right: Treap<usize> = empty();
+        right.insert(100);
+        Treap::merge(left, right);
+    }
+
+    #[test]
+    fn test_sorted_input_stays_shallow() {
+        let mut treap: Treap<usize> = empty();
+        for value in 0..10_000 {
+            treap.insert(value);
+        }
+        assert!(treap.height() < 60);
+        assert_eq!(treap.validate().unwrap().size, 10_000);
+    }
+
+    #[test]
+    fn test_validate_heap_order() {
+        let mut treap = filled_wide::<Treap<usize>>();
+        let root = treap.root.as_mut().unwrap();
+        let priority = root.priority;
+        root.left.as_mut().unwrap().priority = priority + 1;
+        let violation = treap.validate().unwrap_err();
+        assert_eq!(violation.path, vec![Direction::Left]);
+        assert_eq!(violation.kind, ViolationKind::HeapOrder);
+    }
+}
@@ -0,0 +1,278 @@
+// Copyright (C) 2025 BastIsAwesome (bastisawesomeltd@gmail.com)
+//
+// This file is part of deque.
+//
+// deque is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// deque is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with deque.  If not, see <https://www.gnu.org/licenses/>.
+use std::fmt;
+
+use crate::node::BinaryNode;
+
+/// Summary of a tree that passed validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TreeStats {
+    pub size: usize,
+    pub height: usize,
+}
+
+/// Which child was taken when walking down from the root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    /// Child by position, for nodes with more than two children.
+    Child(usize),
+}
+
+/// Why a node failed validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// The node's value falls outside the range allowed by its ancestors.
+    OutOfOrder,
+    /// The node's value equals one of its ancestors.
+    Duplicate,
+    /// The element count stored on the tree or node does not match the
+    /// number of nodes actually reachable from it.
+    SizeMismatch { stored: usize, actual: usize },
+    /// The node's priority is higher than its parent's.
+    HeapOrder,
+    /// The tree is taller than its balancing scheme allows.
+    TooTall { height: usize, limit: usize },
+    /// The node holds more or fewer keys than its order allows.
+    KeyCount {
+        count: usize,
+        min: usize,
+        max: usize,
+    },
+    /// An internal node does not have exactly one more child than keys.
+    ChildCount { keys: usize, children: usize },
+    /// The leaf is not at the same depth as the other leaves.
+    LeafDepth { depth: usize, expected: usize },
+    /// Following the links between leaves does not visit every leaf in
+    /// order.
+    LeafChain,
+    /// A red-black tree that must lean left has a red right link.
+    RedRightLink,
+    /// A red node has a red child.
+    DoubleRed,
+    /// The two subtrees have a different number of black nodes on their
+    /// paths down to the leaves.
+    BlackHeight { left: usize, right: usize },
+    /// An AA tree node's level does not fit the level of the node above it,
+    /// a missing child counting as level zero.
+    Level { level: usize, parent: usize },
+    /// One subtree outweighs the other by more than the balance factor
+    /// allows. Holds the sizes of the left and right subtrees.
+    WeightBalance { left: usize, right: usize },
+}
+
+/// The first invariant violation found while walking a tree.
+///
+/// `path` leads from the root to the offending node, an empty path being the
+/// root itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvariantViolation {
+    pub path: Vec<Direction>,
+    pub kind: ViolationKind,
+}
+
+impl InvariantViolation {
+    pub fn new(path: Vec<Direction>, kind: ViolationKind) -> Self {
+        Self { path, kind }
+    }
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "root")?;
+        for direction in &self.path {
+            match direction {
+                Direction::Left => write!(f, ".left")?,
+                Direction::Right => write!(f, ".right")?,
+                Direction::Child(index) => write!(f, ".children[{index}]")?,
+            }
+        }
+
+        match &self.kind {
+            ViolationKind::OutOfOrder => {
+                write!(f, ": value is out of order with its ancestors")
+            },
+            ViolationKind::Duplicate => {
+                write!(f, ": value duplicates one of its ancestors")
+            },
+            ViolationKind::SizeMismatch { stored, actual } => write!(
+                f,
+                ": stored size is {stored} but {actual} nodes are reachable"
+            ),
+            ViolationKind::HeapOrder => {
+                write!(f, ": priority is higher than its parent's")
+            },
+            ViolationKind::TooTall { height, limit } => {
+                write!(f, ": height is {height} but at most {limit} is allowed")
+            },
+            ViolationKind::KeyCount { count, min, max } => write!(
+                f,
+                ": holds {count} keys but must hold between {min} and {max}"
+            ),
+            ViolationKind::ChildCount { keys, children } => {
+                write!(f, ": has {children} children for {keys} keys")
+            },
+            ViolationKind::LeafDepth { depth, expected } => write!(
+                f,
+                ": leaf is at depth {depth} but the first leaf is at {expected}"
+            ),
+            ViolationKind::LeafChain => {
+                write!(f, ": leaf links do not visit every leaf in order")
+            },
+            ViolationKind::RedRightLink => write!(f, ": right link is red"),
+            ViolationKind::DoubleRed => {
+                write!(f, ": red node has a red child")
+            },
+            ViolationKind::BlackHeight { left, right } => write!(
+                f,
+                ": black height is {left} on the left but {right} on the right"
+            ),
+            ViolationKind::Level { level, parent } => write!(
+                f,
+                ": level {level} is not allowed below a node at level {parent}"
+            ),
+            ViolationKind::WeightBalance { left, right } => write!(
+                f,
+                ": subtrees of {left} and {right} nodes are out of balance"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InvariantViolation {}
+
+/// Checks that every value lies strictly between the nearest ancestors it
+/// must sort after and before, reporting the first offending node in
+/// pre-order.
+pub(crate) fn check_order<N: BinaryNode>(
+    node: &N,
+) -> Result<TreeStats, InvariantViolation>
+where
+    N::Value: PartialOrd,
+{
+    let mut stats = TreeStats::default();
+    let mut path = Vec::new();
+    // Each entry holds the node, its bounds and how it was reached from its
+    // parent. `path` is cut back to the parent before the direction is added.
+    let mut stack =
+        vec![(node, None::<&N::Value>, None::<&N::Value>, 0usize, None)];
+    while let Some((node, lower, upper, depth, direction)) = stack.pop() {
+        path.truncate(depth.saturating_sub(1));
+        path.extend(direction);
+
+        for bound in [lower, upper].into_iter().flatten() {
+            if *node.value() == *bound {
+                return Err(InvariantViolation::new(
+                    path,
+                    ViolationKind::Duplicate,
+                ));
+            }
+        }
+        if lower.is_some_and(|lower| *node.value() < *lower)
+            || upper.is_some_and(|upper| *node.value() > *upper)
+        {
+            return Err(InvariantViolation::new(
+                path,
+                ViolationKind::OutOfOrder,
+            ));
+        }
+
+        stats.size += 1;
+        stats.height = stats.height.max(depth + 1);
+
+        if let Some(right) = node.right() {
+            stack.push((
+                right,
+                Some(node.value()),
+                upper,
+                depth + 1,
+                Some(Direction::Right),
+            ));
+        }
+        if let Some(left) = node.left() {
+            stack.push((
+                left,
+                lower,
+                Some(node.value()),
+                depth + 1,
+                Some(Direction::Left),
+            ));
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Checks an element count stored on a tree or node against the number of
+/// nodes actually reachable from it, reporting a mismatch at `path`.
+pub(crate) fn check_size(
+    path: &[Direction],
+    stored: usize,
+    actual: usize,
+) -> Result<(), InvariantViolation> {
+    match stored == actual {
+        true => Ok(()),
+        false => Err(InvariantViolation::new(
+            path.to_vec(),
+            ViolationKind::SizeMismatch { stored, actual },
+        )),
+    }
+}
+
+/// Number of nodes on the longest path down from `root`, walked with an
+/// explicit stack so degenerate trees cannot overflow the call stack.
+pub(crate) fn height<N: BinaryNode>(root: Option<&N>) -> usize {
+    let mut height = 0;
+    let mut stack: Vec<_> = root.map(|root| (root, 1)).into_iter().collect();
+    while let Some((node, depth)) = stack.pop() {
+        height = height.max(depth);
+        stack.extend(node.left().map(|left| (left, depth + 1)));
+        stack.extend(node.right().map(|right| (right, depth + 1)));
+    }
+    height
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_display_root() {
+        let violation =
+            InvariantViolation::new(vec![], ViolationKind::Duplicate);
+        assert_eq!(
+            violation.to_string(),
+            "root: value duplicates one of its ancestors"
+        );
+    }
+
+    #[test]
+    fn test_display_path() {
+        let violation = InvariantViolation::new(
+            vec![Direction::Left, Direction::Right],
+            ViolationKind::SizeMismatch {
+                stored: 7,
+                actual: 1,
+            },
+        );
+        assert_eq!(
+            violation.to_string(),
+            "root.left.right: stored size is 7 but 1 nodes are reachable"
+        );
+    }
+}
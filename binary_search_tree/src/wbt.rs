@@ -0,0 +1,990 @@
+// Copyright (C) 2025 BastIsAwesome (bastisawesomeltd@gmail.com)
+//
+// This file is part of deque.
+//
+// deque is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// deque is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with deque.  If not, see <https://www.gnu.org/licenses/>.
+use std::fmt::Display;
+use std::{panic, thread};
+
+use crate::node::BinaryNode;
+use crate::render;
+use crate::trace::{NoObserver, TreeObserver};
+use crate::validate::{
+    self, Direction, InvariantViolation, TreeStats, ViolationKind,
+};
+
+type Link<T> = Option<Box<WbtNode<T>>>;
+
+/// Most a subtree may outweigh its sibling by.
+const DELTA: usize = 3;
+/// Below this ratio between the inner and outer grandchild of the heavy side
+/// a single rotation restores balance, otherwise a double rotation is needed.
+const GAMMA: usize = 2;
+/// Subtrees with fewer nodes than this are handled on the current thread by
+/// the parallel operations, where a new thread would cost more than the work
+/// it takes over.
+pub const PAR_THRESHOLD: usize = 1 << 13;
+
+/// Weight-balanced tree in the style of Adams, using the `<3, 2>` parameters
+/// Hirai and Yamamoto proved correct.
+///
+/// Every node stores the size of its subtree. A node's weight is its size
+/// plus one, and neither child of a node may weigh more than three times the
+/// other. The sizes give `select` and `rank` in O(log n), and the balance
+/// rule is cheap to restore when joining two trees of very different sizes,
+/// which is what the set operations are built on: merging `m` values into a
+/// tree of `n` costs O(m log(n / m + 1)) rather than O(m log n).
+///
+/// Insertions and deletions report each step they take, rotations
+/// included, to the tree's [`TreeObserver`]. The set and bulk operations
+/// report nothing.
+pub struct WeightBalancedTree<T: PartialEq + PartialOrd, O = NoObserver> {
+    root: Link<T>,
+    observer: O,
+}
+
+impl<T: PartialEq + PartialOrd> WeightBalancedTree<T> {
+    pub fn new(data: Option<T>) -> Self {
+        let mut tree = Self::with_observer(NoObserver);
+        if let Some(data) = data {
+            tree.insert(data);
+        }
+        tree
+    }
+
+    /// Builds a perfectly balanced tree from values in ascending order,
+    /// building the two halves of large subtrees in parallel. Duplicate
+    /// values are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` is not sorted.
+    pub fn par_from_sorted(mut values: Vec<T>) -> Self
+    where
+        T: Send,
+    {
+        values.dedup();
+        assert!(
+            values.windows(2).all(|pair| pair[0] < pair[1]),
+            "values must be sorted"
+        );
+
+        Self {
+            root: par_from_sorted(values, threads()),
+            observer: NoObserver,
+        }
+    }
+}
+
+impl<T: PartialEq + PartialOrd, O: TreeObserver<T>> WeightBalancedTree<T, O> {
+    pub fn with_observer(observer: O) -> Self {
+        Self {
+            root: None,
+            observer,
+        }
+    }
+
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    pub fn insert(&mut self, value: T) {
+        let root = insert(self.root.take(), value, &mut self.observer);
+        self.root = Some(root);
+    }
+
+    pub fn delete(&mut self, value: T) {
+        self.root = delete(self.root.take(), &value, &mut self.observer);
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let mut node = self.root.as_deref();
+        while let Some(current) = node {
+            if current.data == value {
+                return true;
+            }
+
+            node = if value < current.data {
+                current.left.as_deref()
+            } else {
+                current.right.as_deref()
+            };
+        }
+
+        false
+    }
+
+    pub fn min(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.select(0).cloned()
+    }
+
+    pub fn max(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.select(self.size().checked_sub(1)?).cloned()
+    }
+
+    /// The value with `index` values smaller than it.
+    pub fn select(&self, index: usize) -> Option<&T> {
+        let mut node = self.root.as_deref()?;
+        let mut index = index;
+        loop {
+            let left_size = size(&node.left);
+            if index < left_size {
+                node = node.left.as_deref()?;
+            } else if index == left_size {
+                return Some(&node.data);
+            } else {
+                index -= left_size + 1;
+                node = node.right.as_deref()?;
+            }
+        }
+    }
+
+    /// Number of values less than `value`, whether or not it is present.
+    pub fn rank(&self, value: T) -> usize {
+        let mut rank = 0;
+        let mut node = self.root.as_deref();
+        while let Some(current) = node {
+            if value <= current.data {
+                node = current.left.as_deref();
+            } else {
+                rank += size(&current.left) + 1;
+                node = current.right.as_deref();
+            }
+        }
+        rank
+    }
+
+    /// Values in either tree. The result keeps this tree's observer.
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            root: union(self.root, other.root),
+            observer: self.observer,
+        }
+    }
+
+    /// Values in both trees. The result keeps this tree's observer.
+    pub fn intersection(self, other: Self) -> Self {
+        Self {
+            root: intersection(self.root, other.root),
+            observer: self.observer,
+        }
+    }
+
+    /// Values in this tree but not in `other`. The result keeps this tree's
+    /// observer.
+    pub fn difference(self, other: Self) -> Self {
+        Self {
+            root: difference(self.root, other.root),
+            observer: self.observer,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn size(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn height(&self) -> usize {
+        validate::height(self.root.as_deref())
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+    }
+
+    /// Checks the search tree order, the subtree size stored on every node
+    /// and the weight balance between every pair of siblings.
+    pub fn validate(&self) -> Result<TreeStats, InvariantViolation> {
+        let Some(root) = &self.root else {
+            return Ok(TreeStats::default());
+        };
+
+        let stats = validate::check_order(root.as_ref())?;
+        check_node(root, &mut Vec::new())?;
+        Ok(stats)
+    }
+}
+
+/// Bulk operations that split the work of subtrees of at least
+/// [`PAR_THRESHOLD`] nodes between threads, using no more threads than
+/// [`thread::available_parallelism`] reports. Smaller trees, and any work
+/// left once the threads are used up, are handled exactly as the sequential
+/// operations would.
+impl<T, O> WeightBalancedTree<T, O>
+where
+    T: PartialEq + PartialOrd + Send,
+    O: TreeObserver<T>,
+{
+    /// Calls `f` on every value, visiting the two sides of large subtrees in
+    /// parallel, so the values are seen in no particular order.
+    pub fn par_for_each<F: Fn(&T) + Sync>(&self, f: F)
+    where
+        T: Sync,
+    {
+        par_for_each(&self.root, &f, threads());
+    }
+
+    /// Like [`WeightBalancedTree::union`], with the halves on either side of
+    /// each split merged in parallel.
+    pub fn par_union(self, other: Self) -> Self {
+        Self {
+            root: par_union(self.root, other.root, threads()),
+            observer: self.observer,
+        }
+    }
+
+    /// Like [`WeightBalancedTree::intersection`], with the halves on either
+    /// side of each split intersected in parallel.
+    pub fn par_intersection(self, other: Self) -> Self {
+        Self {
+            root: par_intersection(self.root, other.root, threads()),
+            observer: self.observer,
+        }
+    }
+}
+
+impl<T: PartialEq + PartialOrd + Display, O> WeightBalancedTree<T, O> {
+    pub fn to_dot(&self) -> String {
+        render::to_dot("WeightBalancedTree", self.root.as_deref())
+    }
+
+    pub fn render_ascii(&self) -> String {
+        render::render_ascii(self.root.as_deref())
+    }
+}
+
+fn size<T>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+fn weight<T>(link: &Link<T>) -> usize {
+    size(link) + 1
+}
+
+fn node<T>(left: Link<T>, data: T, right: Link<T>) -> Box<WbtNode<T>> {
+    let mut node = Box::new(WbtNode::new(data));
+    node.left = left;
+    node.right = right;
+    node.update_size();
+    node
+}
+
+fn rotate_left<T, O: TreeObserver<T>>(
+    mut node: Box<WbtNode<T>>,
+    observer: &mut O,
+) -> Box<WbtNode<T>> {
+    observer.on_rotate(Direction::Left, &node.data);
+    let mut right = node.right.take().unwrap();
+    node.right = right.left.take();
+    node.update_size();
+    right.left = Some(node);
+    right.update_size();
+    right
+}
+
+fn rotate_right<T, O: TreeObserver<T>>(
+    mut node: Box<WbtNode<T>>,
+    observer: &mut O,
+) -> Box<WbtNode<T>> {
+    observer.on_rotate(Direction::Right, &node.data);
+    let mut left = node.left.take().unwrap();
+    node.left = left.right.take();
+    node.update_size();
+    left.right = Some(node);
+    left.update_size();
+    left
+}
+
+/// Restores the weight balance at `node` after one side grew or shrank by
+/// a bounded amount, and refreshes its size.
+fn balance<T, O: TreeObserver<T>>(
+    mut node: Box<WbtNode<T>>,
+    observer: &mut O,
+) -> Box<WbtNode<T>> {
+    let (left, right) = (weight(&node.left), weight(&node.right));
+    if DELTA * left < right {
+        let heavy = node.right.as_ref().unwrap();
+        if weight(&heavy.left) >= GAMMA * weight(&heavy.right) {
+            let heavy = node.right.take().unwrap();
+            node.right = Some(rotate_right(heavy, observer));
+        }
+        return rotate_left(node, observer);
+    }
+    if DELTA * right < left {
+        let heavy = node.left.as_ref().unwrap();
+        if weight(&heavy.right) >= GAMMA * weight(&heavy.left) {
+            let heavy = node.left.take().unwrap();
+            node.left = Some(rotate_left(heavy, observer));
+        }
+        return rotate_right(node, observer);
+    }
+
+    node.update_size();
+    node
+}
+
+fn insert<T: PartialOrd, O: TreeObserver<T>>(
+    link: Link<T>,
+    value: T,
+    observer: &mut O,
+) -> Box<WbtNode<T>> {
+    let Some(mut node) = link else {
+        observer.on_link(&value);
+        return Box::new(WbtNode::new(value));
+    };
+
+    observer.on_compare(&value, &node.data);
+    if value < node.data {
+        observer.on_descend(Direction::Left);
+        node.left = Some(insert(node.left.take(), value, observer));
+    } else if value > node.data {
+        observer.on_descend(Direction::Right);
+        node.right = Some(insert(node.right.take(), value, observer));
+    } else {
+        return node;
+    }
+
+    balance(node, observer)
+}
+
+fn delete<T: PartialOrd, O: TreeObserver<T>>(
+    link: Link<T>,
+    value: &T,
+    observer: &mut O,
+) -> Link<T> {
+    // Value does not exist.
+    let mut node = link?;
+
+    observer.on_compare(value, &node.data);
+    if *value < node.data {
+        observer.on_descend(Direction::Left);
+        node.left = delete(node.left.take(), value, observer);
+    } else if *value > node.data {
+        observer.on_descend(Direction::Right);
+        node.right = delete(node.right.take(), value, observer);
+    } else {
+        observer.on_unlink(&node.data);
+        return merge(node.left.take(), node.right.take(), observer);
+    }
+
+    Some(balance(node, observer))
+}
+
+/// Removes the smallest value of the subtree, returning what is left of it
+/// and the value.
+fn take_min<T, O: TreeObserver<T>>(
+    mut node: Box<WbtNode<T>>,
+    observer: &mut O,
+) -> (Link<T>, T) {
+    let Some(left) = node.left.take() else {
+        return (node.right.take(), node.data);
+    };
+
+    observer.on_descend(Direction::Left);
+    let (left, min) = take_min(left, observer);
+    node.left = left;
+    (Some(balance(node, observer)), min)
+}
+
+/// Joins `left`, `data` and `right`, where every value in `left` sorts
+/// before `data` and every value in `right` after it, however different
+/// their sizes. Walks down the spine of the heavier side until the weights
+/// are comparable and rebalances on the way back up.
+fn link<T>(left: Link<T>, data: T, right: Link<T>) -> Box<WbtNode<T>> {
+    if DELTA * weight(&left) < weight(&right) {
+        let mut right = right.unwrap();
+        right.left = Some(link(left, data, right.left.take()));
+        return balance(right, &mut NoObserver);
+    }
+    if DELTA * weight(&right) < weight(&left) {
+        let mut left = left.unwrap();
+        left.right = Some(link(left.right.take(), data, right));
+        return balance(left, &mut NoObserver);
+    }
+
+    node(left, data, right)
+}
+
+/// Like [`link`] without a value in the middle.
+fn merge<T, O: TreeObserver<T>>(
+    left: Link<T>,
+    right: Link<T>,
+    observer: &mut O,
+) -> Link<T> {
+    let (mut left, mut right) = match (left, right) {
+        (None, right) => return right,
+        (left, None) => return left,
+        (Some(left), Some(right)) => (left, right),
+    };
+
+    if DELTA * (left.size + 1) < right.size + 1 {
+        observer.on_descend(Direction::Left);
+        right.left = merge(Some(left), right.left.take(), observer);
+        return Some(balance(right, observer));
+    }
+    if DELTA * (right.size + 1) < left.size + 1 {
+        observer.on_descend(Direction::Right);
+        left.right = merge(left.right.take(), Some(right), observer);
+        return Some(balance(left, observer));
+    }
+
+    observer.on_descend(Direction::Right);
+    let (right, min) = take_min(right, observer);
+    Some(balance(node(Some(left), min, right), observer))
+}
+
+/// Splits the subtree into the values below `key` and those above it, and
+/// reports whether `key` itself was there. The matching value is dropped.
+fn split<T: PartialOrd>(link: Link<T>, key: &T) -> (Link<T>, bool, Link<T>) {
+    let Some(node) = link else {
+        return (None, false, None);
+    };
+
+    let WbtNode {
+        data, left, right, ..
+    } = *node;
+    if *key < data {
+        let (less, found, greater) = split(left, key);
+        (less, found, Some(self::link(greater, data, right)))
+    } else if *key > data {
+        let (less, found, greater) = split(right, key);
+        (Some(self::link(left, data, less)), found, greater)
+    } else {
+        (left, true, right)
+    }
+}
+
+/// Splits `b` around the root of `a` and joins the unions of the halves back
+/// under it. Once either side runs out the other is returned whole, which
+/// is what keeps a small tree cheap to merge into a large one.
+fn union<T: PartialOrd>(a: Link<T>, b: Link<T>) -> Link<T> {
+    let (a, b) = match (a, b) {
+        (None, b) => return b,
+        (a, None) => return a,
+        (Some(a), b) => (a, b),
+    };
+
+    let WbtNode {
+        data, left, right, ..
+    } = *a;
+    let (less, _, greater) = split(b, &data);
+    Some(link(union(left, less), data, union(right, greater)))
+}
+
+fn intersection<T: PartialOrd>(a: Link<T>, b: Link<T>) -> Link<T> {
+    let (Some(a), Some(b)) = (a, b) else {
+        return None;
+    };
+
+    let WbtNode {
+        data, left, right, ..
+    } = *a;
+    let (less, found, greater) = split(Some(b), &data);
+    let left = intersection(left, less);
+    let right = intersection(right, greater);
+    match found {
+        true => Some(link(left, data, right)),
+        false => merge(left, right, &mut NoObserver),
+    }
+}
+
+/// Values of `a` that are not in `b`.
+fn difference<T: PartialOrd>(a: Link<T>, b: Link<T>) -> Link<T> {
+    let (a, b) = match (a, b) {
+        (None, _) => return None,
+        (a, None) => return a,
+        (a, Some(b)) => (a, b),
+    };
+
+    let WbtNode {
+        data, left, right, ..
+    } = *b;
+    let (less, _, greater) = split(a, &data);
+    let (less, greater) = (difference(less, left), difference(greater, right));
+    merge(less, greater, &mut NoObserver)
+}
+
+/// Threads the parallel operations may use between them, counting the one
+/// they are called on.
+fn threads() -> usize {
+    thread::available_parallelism().map_or(1, |threads| threads.get())
+}
+
+/// Runs `a` on a new thread while `b` runs on this one if `threads` allows a
+/// second thread, and both on this one otherwise. Each side is handed its
+/// share of the budget, so no more than `threads` ever run at once. A panic
+/// in either is passed on.
+fn join<A, B, RA, RB>(threads: usize, a: A, b: B) -> (RA, RB)
+where
+    A: FnOnce(usize) -> RA + Send,
+    B: FnOnce(usize) -> RB,
+    RA: Send,
+{
+    if threads < 2 {
+        return (a(1), b(1));
+    }
+
+    let (a_threads, b_threads) = (threads / 2, threads - threads / 2);
+    thread::scope(|scope| {
+        let a = scope.spawn(move || a(a_threads));
+        let b = b(b_threads);
+        let a = a
+            .join()
+            .unwrap_or_else(|payload| panic::resume_unwind(payload));
+        (a, b)
+    })
+}
+
+/// Builds a perfectly balanced tree from the next `len` values, which must
+/// be in ascending order.
+fn from_sorted<T, I: Iterator<Item = T>>(
+    values: &mut I,
+    len: usize,
+) -> Link<T> {
+    if len == 0 {
+        return None;
+    }
+
+    let left = from_sorted(values, len / 2);
+    let data = values.next().unwrap();
+    let right = from_sorted(values, len - len / 2 - 1);
+    Some(node(left, data, right))
+}
+
+/// Splits the values the same way [`from_sorted`] would, handing the left
+/// half to another thread while the halves are large and threads remain.
+/// Each split moves the upper half into a new `Vec`, which only happens a
+/// logarithmic number of times in the thread count.
+fn par_from_sorted<T: Send>(mut values: Vec<T>, threads: usize) -> Link<T> {
+    let len = values.len();
+    if len < PAR_THRESHOLD || threads < 2 {
+        return from_sorted(&mut values.into_iter(), len);
+    }
+
+    let right = values.split_off(len / 2 + 1);
+    // The middle value, now the last of the lower half.
+    let data = values.pop().unwrap();
+    let left = values;
+    let (left, right) = join(
+        threads,
+        move |threads| par_from_sorted(left, threads),
+        move |threads| par_from_sorted(right, threads),
+    );
+    Some(node(left, data, right))
+}
+
+fn par_for_each<T: Sync, F: Fn(&T) + Sync>(
+    link: &Link<T>,
+    f: &F,
+    threads: usize,
+) {
+    let Some(node) = link else {
+        return;
+    };
+
+    let threads = match node.size >= PAR_THRESHOLD {
+        true => threads,
+        false => 1,
+    };
+    join(
+        threads,
+        |threads| par_for_each(&node.left, f, threads),
+        |threads| par_for_each(&node.right, f, threads),
+    );
+    f(&node.data);
+}
+
+/// [`union`], with the two recursive calls run in parallel while the trees
+/// are large and threads remain.
+fn par_union<T: PartialOrd + Send>(
+    a: Link<T>,
+    b: Link<T>,
+    threads: usize,
+) -> Link<T> {
+    if size(&a) + size(&b) < PAR_THRESHOLD || threads < 2 {
+        return union(a, b);
+    }
+    let (a, b) = match (a, b) {
+        (None, b) => return b,
+        (a, None) => return a,
+        (Some(a), b) => (a, b),
+    };
+
+    let WbtNode {
+        data, left, right, ..
+    } = *a;
+    let (less, _, greater) = split(b, &data);
+    let (left, right) = join(
+        threads,
+        move |threads| par_union(left, less, threads),
+        move |threads| par_union(right, greater, threads),
+    );
+    Some(link(left, data, right))
+}
+
+/// [`intersection`], with the two recursive calls run in parallel while the
+/// trees are large and threads remain.
+fn par_intersection<T: PartialOrd + Send>(
+    a: Link<T>,
+    b: Link<T>,
+    threads: usize,
+) -> Link<T> {
+    if size(&a) + size(&b) < PAR_THRESHOLD || threads < 2 {
+        return intersection(a, b);
+    }
+    let (Some(a), Some(b)) = (a, b) else {
+        return None;
+    };
+
+    let WbtNode {
+        data, left, right, ..
+    } = *a;
+    let (less, found, greater) = split(Some(b), &data);
+    let (left, right) = join(
+        threads,
+        move |threads| par_intersection(left, less, threads),
+        move |threads| par_intersection(right, greater, threads),
+    );
+    match found {
+        true => Some(link(left, data, right)),
+        false => merge(left, right, &mut NoObserver),
+    }
+}
+
+/// Checks stored sizes and the weight balance below `node`, returning the
+/// number of nodes in the subtree.
+fn check_node<T>(
+    node: &WbtNode<T>,
+    path: &mut Vec<Direction>,
+) -> Result<usize, InvariantViolation> {
+    let mut sizes = [0; 2];
+    for (size, (direction, child)) in sizes.iter_mut().zip([
+        (Direction::Left, &node.left),
+        (Direction::Right, &node.right),
+    ]) {
+        let Some(child) = child else {
+            continue;
+        };
+
+        path.push(direction);
+        *size = check_node(child, path)?;
+        path.pop();
+    }
+
+    let [left, right] = sizes;
+    validate::check_size(path, node.size, left + right + 1)?;
+    if DELTA * (left + 1) < right + 1 || DELTA * (right + 1) < left + 1 {
+        return Err(InvariantViolation::new(
+            path.clone(),
+            ViolationKind::WeightBalance { left, right },
+        ));
+    }
+
+    Ok(node.size)
+}
+
+struct WbtNode<T> {
+    left: Link<T>,
+    right: Link<T>,
+    data: T,
+    /// Number of nodes in the subtree rooted here, this one included.
+    size: usize,
+}
+
+impl<T> WbtNode<T> {
+    fn new(data: T) -> Self {
+        Self {
+            left: None,
+            right: None,
+            data,
+            size: 1,
+        }
+    }
+
+    fn update_size(&mut self) {
+        self.size = 1 + size(&self.left) + size(&self.right);
+    }
+}
+
+impl<T> BinaryNode for WbtNode<T> {
+    type Value = T;
+
+    fn value(&self) -> &T {
+        &self.data
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::conformance::{
+        self, conformance_tests, empty, filled, filled_wide, impl_test_set,
+    };
+    use crate::rng::Rng;
+    use std::collections::{BTreeSet, HashSet};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    type Wbt = WeightBalancedTree<usize>;
+
+    impl_test_set!(
+        WeightBalancedTree,
+        |_| WeightBalancedTree::new(None),
+        delete
+    );
+    conformance_tests!(Wbt);
+
+    fn from_values<I: IntoIterator<Item = usize>>(values: I) -> Wbt {
+        conformance::from_values(values)
+    }
+
+    fn values(tree: &Wbt) -> Vec<usize> {
+        (0..tree.size()).map(|i| *tree.select(i).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut tree = filled_wide::<Wbt>();
+        tree.insert(42);
+        assert_eq!(tree.size(), 7);
+        assert_eq!(values(&tree), [17, 25, 42, 50, 75, 100, 150]);
+        assert_eq!(tree.validate().unwrap().size, 7);
+    }
+
+    #[test]
+    fn test_delete() {
+        let mut tree = filled_wide::<Wbt>();
+        tree.delete(50);
+        tree.delete(17);
+        tree.delete(255);
+        assert!(!tree.contains(50));
+        assert!(tree.contains(42));
+        assert_eq!(values(&tree), [25, 42, 75, 100, 150]);
+        assert!(tree.validate().is_ok());
+    }
+
+    #[test]
+    fn test_select_rank() {
+        let tree = filled_wide::<Wbt>();
+        assert_eq!(tree.select(0), Some(&17));
+        assert_eq!(tree.select(3), Some(&50));
+        assert_eq!(tree.select(7), None);
+        assert_eq!(tree.rank(17), 0);
+        assert_eq!(tree.rank(50), 3);
+        assert_eq!(tree.rank(51), 4);
+        assert_eq!(tree.rank(1000), 7);
+    }
+
+    #[test]
+    fn test_sorted_input_is_balanced() {
+        let tree = from_values(0..1023);
+        // A weight ratio of 3 allows at most log(n) / log(4 / 3) levels.
+        assert!(tree.height() <= 25);
+        assert_eq!(tree.validate().unwrap().size, 1023);
+    }
+
+    #[test]
+    fn test_union() {
+        let tree = filled_wide::<Wbt>().union(from_values([1, 50, 60, 200]));
+        assert_eq!(values(&tree), [1, 17, 25, 42, 50, 60, 75, 100, 150, 200]);
+        assert!(tree.validate().is_ok());
+    }
+
+    #[test]
+    fn test_intersection() {
+        let tree = filled_wide::<Wbt>().intersection(from_values([1, 50, 150]));
+        assert_eq!(values(&tree), [50, 150]);
+        assert!(tree.validate().is_ok());
+    }
+
+    #[test]
+    fn test_difference() {
+        let tree = filled_wide::<Wbt>().difference(from_values([1, 50, 150]));
+        assert_eq!(values(&tree), [17, 25, 42, 75, 100]);
+        assert!(tree.validate().is_ok());
+    }
+
+    #[test]
+    fn test_union_small_into_large() {
+        let base = from_values((0..100_000).map(|value| value * 2));
+        let tree = base.union(from_values([1, 3, 199_999]));
+        assert_eq!(tree.size(), 100_003);
+        assert_eq!(tree.rank(4), 4);
+        assert!(tree.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_weight_balance() {
+        // 100 has nothing on its left and three nodes on its right.
+        //   50
+        //  /  \
+        // 25   100
+        //         \
+        //         200
+        //           \
+        //           300
+        //             \
+        //             400
+        let mut tree: Wbt = filled();
+        let mut node = tree.root.as_mut().unwrap();
+        node.size = 6;
+        node = node.right.as_mut().unwrap();
+        for (size, value) in [(4, 200), (3, 300), (2, 400)] {
+            node.size = size;
+            node = node.right.insert(Box::new(WbtNode::new(value)));
+        }
+
+        let violation = tree.validate().unwrap_err();
+        assert_eq!(violation.path, vec![Direction::Right]);
+        assert_eq!(
+            violation.kind,
+            ViolationKind::WeightBalance { left: 0, right: 3 }
+        );
+    }
+
+    #[test]
+    fn test_set_ops_match_btree_set() {
+        for seed in 1..=32 {
+            let (tree, set) = conformance::random_ops::<Wbt>(seed);
+            for value in 0..64 {
+                assert_eq!(tree.rank(value), set.range(..value).count());
+            }
+
+            let mut rng = Rng::new(!seed);
+            let other: BTreeSet<usize> =
+                (0..40).map(|_| (rng.next_u64() % 96) as usize).collect();
+            let expected: Vec<_> = set.union(&other).copied().collect();
+            let tree = tree.union(from_values(other.iter().copied()));
+            assert_eq!(values(&tree), expected, "seed {seed}");
+            assert!(tree.validate().is_ok(), "seed {seed}");
+
+            let both: BTreeSet<_> = set.union(&other).copied().collect();
+            let expected: Vec<_> = both.intersection(&set).copied().collect();
+            let kept = tree.intersection(from_values(set.iter().copied()));
+            assert_eq!(values(&kept), expected, "seed {seed}");
+            assert!(kept.validate().is_ok(), "seed {seed}");
+
+            let expected: Vec<_> = set.difference(&other).copied().collect();
+            let rest = kept.difference(from_values(other.iter().copied()));
+            assert_eq!(values(&rest), expected, "seed {seed}");
+            assert!(rest.validate().is_ok(), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn test_par_from_sorted() {
+        let mut sorted: Vec<usize> = (0..100_000).collect();
+        sorted.extend(0..10);
+        sorted.sort();
+        let tree = WeightBalancedTree::par_from_sorted(sorted);
+        assert_eq!(values(&tree), (0..100_000).collect::<Vec<_>>());
+        // Perfectly balanced, so no deeper than log2(n + 1) rounded up.
+        assert_eq!(tree.height(), 17);
+        assert_eq!(tree.validate().unwrap().size, 100_000);
+
+        let tree = WeightBalancedTree::par_from_sorted(vec![1, 2, 3]);
+        assert_eq!(values(&tree), [1, 2, 3]);
+        assert!(WeightBalancedTree::<usize>::par_from_sorted(vec![]).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_par_from_sorted_unsorted() {
+        WeightBalancedTree::par_from_sorted(vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn test_par_for_each() {
+        let tree = from_values(0..50_000);
+        let count = AtomicUsize::new(0);
+        let sum = AtomicUsize::new(0);
+        tree.par_for_each(|value| {
+            count.fetch_add(1, Ordering::Relaxed);
+            sum.fetch_add(*value, Ordering::Relaxed);
+        });
+        assert_eq!(count.into_inner(), 50_000);
+        assert_eq!(sum.into_inner(), (0..50_000).sum());
+
+        empty::<Wbt>().par_for_each(|_| panic!("empty tree has no values"));
+    }
+
+    #[test]
+    fn test_par_thread_budget() {
+        assert_eq!(join(1, |threads| threads, |threads| threads), (1, 1));
+        assert_eq!(join(5, |threads| threads, |threads| threads), (2, 3));
+
+        // Large enough to split at every level the budget allows.
+        let tree = from_values(0..200_000);
+        let seen = Mutex::new(HashSet::new());
+        par_for_each(
+            &tree.root,
+            &|_: &usize| {
+                seen.lock().unwrap().insert(thread::current().id());
+            },
+            3,
+        );
+        assert_eq!(seen.into_inner().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_par_union_intersection() {
+        let mut rng = Rng::new(7);
+        let mut random = |count: usize| -> BTreeSet<usize> {
+            (0..count)
+                .map(|_| (rng.next_u64() % 200_000) as usize)
+                .collect()
+        };
+        let (a, b, small) = (random(60_000), random(40_000), random(50));
+        let tree = |set: &BTreeSet<usize>| {
+            WeightBalancedTree::par_from_sorted(set.iter().copied().collect())
+        };
+
+        let union = tree(&a).par_union(tree(&b));
+        let expected: Vec<_> = a.union(&b).copied().collect();
+        assert_eq!(values(&union), expected);
+        assert!(union.validate().is_ok());
+
+        let both = tree(&a).par_intersection(tree(&b));
+        let expected: Vec<_> = a.intersection(&b).copied().collect();
+        assert_eq!(values(&both), expected);
+        assert!(both.validate().is_ok());
+
+        // Below the threshold the sequential versions do the work.
+        let union = tree(&small).par_union(tree(&small));
+        assert_eq!(values(&union), small.iter().copied().collect::<Vec<_>>());
+        let both = tree(&small).par_intersection(empty::<Wbt>());
+        assert!(both.is_empty());
+    }
+}
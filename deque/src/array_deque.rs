@@ -0,0 +1,529 @@
+// Copyright (C) 2025 BastIsAwesome (bastisawesomeltd@gmail.com)
+//
+// This file is part of deque.
+//
+// deque is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// deque is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with deque.  If not, see <https://www.gnu.org/licenses/>.
+use core::iter::FusedIterator;
+use core::mem::MaybeUninit;
+use core::ops::{Index, IndexMut};
+use core::slice;
+
+use super::Deque;
+
+/// Deque of at most `N` elements stored inline in a ring, so it never
+/// allocates. Pushing onto a full deque hands the element back instead of
+/// growing.
+///
+/// Through the [`Deque`] trait, whose pushes can't fail, pushing onto a
+/// full deque panics. Use [`Deque::try_push_front`] and
+/// [`Deque::try_push_back`] in code generic over deques.
+pub struct ArrayDeque<T, const N: usize> {
+    buffer: [MaybeUninit<T>; N],
+    /// Slot of the front element.
+    first: usize,
+    len: usize,
+}
+
+impl<T, const N: usize> ArrayDeque<T, N> {
+    pub const fn new() -> Self {
+        Self {
+            buffer: [const { MaybeUninit::uninit() }; N],
+            first: 0,
+            len: 0,
+        }
+    }
+
+    /// Most elements the deque can hold, which is always `N`.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Pushes `element` onto the front, or hands it back if the deque is
+    /// full.
+    pub fn push_front(&mut self, element: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(element);
+        }
+
+        self.first = match self.first == 0 {
+            true => N - 1,
+            false => self.first - 1,
+        };
+        self.buffer[self.first].write(element);
+        self.len += 1;
+
+        Ok(())
+    }
+
+    /// Pushes `element` onto the back, or hands it back if the deque is
+    /// full.
+    pub fn push_back(&mut self, element: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(element);
+        }
+
+        let slot = self.slot(self.len);
+        self.buffer[slot].write(element);
+        self.len += 1;
+
+        Ok(())
+    }
+
+    /// Slot `index` places on from the front, wrapping at most once.
+    fn slot(&self, index: usize) -> usize {
+        let slot = self.first + index;
+        match slot >= N {
+            true => slot - N,
+            false => slot,
+        }
+    }
+
+    /// The slots holding elements, front part first. The second part is
+    /// only non-empty when the elements wrap past the end of the buffer.
+    fn slots(&self) -> (&[MaybeUninit<T>], &[MaybeUninit<T>]) {
+        match self.first + self.len <= N {
+            true => (&self.buffer[self.first..self.first + self.len], &[]),
+            false => {
+                let end = self.first + self.len - N;
+                (&self.buffer[self.first..], &self.buffer[..end])
+            },
+        }
+    }
+
+    fn slots_mut(&mut self) -> (&mut [MaybeUninit<T>], &mut [MaybeUninit<T>]) {
+        match self.first + self.len <= N {
+            true => {
+                (&mut self.buffer[self.first..self.first + self.len], &mut [])
+            },
+            false => {
+                let end = self.first + self.len - N;
+                let (wrapped, front) = self.buffer.split_at_mut(self.first);
+                (front, &mut wrapped[..end])
+            },
+        }
+    }
+}
+
+impl<T, const N: usize> Default for ArrayDeque<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Deque<T> for ArrayDeque<T, N> {
+    type Iter<'a>
+        = Iter<'a, T>
+    where
+        T: 'a;
+    type IterMut<'a>
+        = IterMut<'a, T>
+    where
+        T: 'a;
+
+    /// # Panics
+    ///
+    /// Panics if the deque is full.
+    fn push_front(&mut self, element: T) {
+        if ArrayDeque::push_front(self, element).is_err() {
+            panic!("pushed onto a full ArrayDeque of capacity {N}");
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the deque is full.
+    fn push_back(&mut self, element: T) {
+        if ArrayDeque::push_back(self, element).is_err() {
+            panic!("pushed onto a full ArrayDeque of capacity {N}");
+        }
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+
+        // SAFETY: the deque isn't empty, so `first` holds an element, and
+        // moving `first` past it leaves nothing else that reads it.
+        let elem = unsafe { self.buffer[self.first].assume_init_read() };
+        self.first = self.slot(1);
+        self.len -= 1;
+
+        Some(elem)
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+
+        self.len -= 1;
+        let slot = self.slot(self.len);
+        // SAFETY: as in `pop_front`, for the slot of the back element.
+        Some(unsafe { self.buffer[slot].assume_init_read() })
+    }
+
+    fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    fn back(&self) -> Option<&T> {
+        self.get(self.len.checked_sub(1)?)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn iter(&self) -> Iter<'_, T> {
+        let (front, back) = self.slots();
+
+        Iter {
+            front: front.iter(),
+            back: back.iter(),
+        }
+    }
+
+    fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let (front, back) = self.slots_mut();
+
+        IterMut {
+            front: front.iter_mut(),
+            back: back.iter_mut(),
+        }
+    }
+
+    fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+
+        // SAFETY: slots of in-bounds indices hold elements.
+        Some(unsafe { self.buffer[self.slot(index)].assume_init_ref() })
+    }
+
+    fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len {
+            return None;
+        }
+
+        let slot = self.slot(index);
+        // SAFETY: as in `get`.
+        Some(unsafe { self.buffer[slot].assume_init_mut() })
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        assert!(i < self.len && j < self.len, "index out of bounds");
+        let (i, j) = (self.slot(i), self.slot(j));
+        self.buffer.swap(i, j);
+    }
+
+    fn try_push_front(&mut self, element: T) -> Result<(), T> {
+        ArrayDeque::push_front(self, element)
+    }
+
+    fn try_push_back(&mut self, element: T) -> Result<(), T> {
+        ArrayDeque::push_back(self, element)
+    }
+}
+
+impl<T, const N: usize> Index<usize> for ArrayDeque<T, N> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<T, const N: usize> IndexMut<usize> for ArrayDeque<T, N> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.get_mut(index).expect("index out of bounds")
+    }
+}
+
+/// The buffer is plain memory, so the elements still in it are dropped
+/// here.
+impl<T, const N: usize> Drop for ArrayDeque<T, N> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+impl<T, const N: usize> IntoIterator for ArrayDeque<T, N> {
+    type Item = T;
+    type IntoIter = IntoIter<T, N>;
+
+    fn into_iter(self) -> IntoIter<T, N> {
+        IntoIter { deque: self }
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a ArrayDeque<T, N> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut ArrayDeque<T, N> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+/// Borrows the elements front to back.
+pub struct Iter<'a, T> {
+    front: slice::Iter<'a, MaybeUninit<T>>,
+    back: slice::Iter<'a, MaybeUninit<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let slot = self.front.next().or_else(|| self.back.next())?;
+        // SAFETY: the iterator only covers slots that hold elements, and the
+        // deque is borrowed for as long as the references live.
+        Some(unsafe { slot.assume_init_ref() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.front.len() + self.back.len();
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let slot = self.back.next_back().or_else(|| self.front.next_back())?;
+        // SAFETY: as in `next`.
+        Some(unsafe { slot.assume_init_ref() })
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+/// Mutably borrows the elements front to back.
+pub struct IterMut<'a, T> {
+    front: slice::IterMut<'a, MaybeUninit<T>>,
+    back: slice::IterMut<'a, MaybeUninit<T>>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        let slot = self.front.next().or_else(|| self.back.next())?;
+        // SAFETY: as for `Iter`, and every slot is handed out only once.
+        Some(unsafe { slot.assume_init_mut() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.front.len() + self.back.len();
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let slot = self.back.next_back().or_else(|| self.front.next_back())?;
+        // SAFETY: as in `next`.
+        Some(unsafe { slot.assume_init_mut() })
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+/// Moves the elements out front to back. Whatever is left is dropped with
+/// the iterator.
+pub struct IntoIter<T, const N: usize> {
+    deque: ArrayDeque<T, N>,
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.deque.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.deque.len(), Some(self.deque.len()))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IntoIter<T, N> {
+    fn next_back(&mut self) -> Option<T> {
+        self.deque.pop_back()
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+
+impl<T, const N: usize> FusedIterator for IntoIter<T, N> {}
+
+#[cfg(test)]
+mod test {
+    use super::ArrayDeque;
+    use crate::Deque;
+
+    type UDeque = ArrayDeque<usize, 4>;
+
+    fn values<const N: usize>(deque: ArrayDeque<usize, N>) -> Vec<usize> {
+        deque.into_iter().collect()
+    }
+
+    /// Pushes through the trait alone, as code generic over deques would.
+    fn fill<D: Deque<usize>>(deque: &mut D, count: usize) -> Option<usize> {
+        for value in 0..count {
+            if let Err(value) = deque.try_push_back(value) {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn test_push_pop() {
+        let mut deque = UDeque::new();
+        assert!(deque.is_empty());
+        assert_eq!(deque.capacity(), 4);
+        assert_eq!(deque.push_back(2), Ok(()));
+        assert_eq!(deque.push_front(1), Ok(()));
+        assert_eq!(deque.push_back(3), Ok(()));
+        assert_eq!(deque.front(), Some(&1));
+        assert_eq!(deque.back(), Some(&3));
+        assert_eq!(deque.pop_front(), Some(1));
+        assert_eq!(deque.pop_back(), Some(3));
+        assert_eq!(deque.pop_back(), Some(2));
+        assert_eq!(deque.pop_back(), None);
+        assert_eq!(deque.pop_front(), None);
+        assert_eq!(deque.back(), None);
+    }
+
+    #[test]
+    fn test_full() {
+        let mut deque = UDeque::new();
+        assert_eq!(fill(&mut deque, 10), Some(4));
+        assert!(deque.is_full());
+        assert_eq!(deque.push_back(4), Err(4));
+        assert_eq!(deque.push_front(5), Err(5));
+        assert_eq!(deque.try_push_front(5), Err(5));
+
+        assert_eq!(deque.pop_front(), Some(0));
+        assert_eq!(deque.push_back(4), Ok(()));
+        assert_eq!(values(deque), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "full ArrayDeque")]
+    fn test_trait_push_panics_when_full() {
+        let mut deque = ArrayDeque::<usize, 1>::new();
+        Deque::push_back(&mut deque, 1);
+        Deque::push_front(&mut deque, 2);
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        let mut deque = ArrayDeque::<usize, 0>::new();
+        assert!(deque.is_full());
+        assert_eq!(deque.push_front(1), Err(1));
+        assert_eq!(deque.iter().next(), None);
+        assert_eq!(deque.pop_back(), None);
+    }
+
+    #[test]
+    fn test_wraps() {
+        let mut deque = UDeque::new();
+        for round in 0..7 {
+            deque.push_back(round).unwrap();
+            assert_eq!(deque.pop_front(), Some(round));
+        }
+        deque.push_front(101).unwrap();
+        deque.push_front(100).unwrap();
+        deque.push_back(102).unwrap();
+        deque.push_back(103).unwrap();
+        assert_eq!(deque[0], 100);
+        assert_eq!(deque.get(3), Some(&103));
+        assert_eq!(deque.get(4), None);
+
+        deque[1] = 0;
+        deque.swap(0, 3);
+        assert_eq!(
+            deque.iter().rev().copied().collect::<Vec<_>>(),
+            [100, 102, 0, 103]
+        );
+        for value in &mut deque {
+            *value += 1;
+        }
+        let mut iter = deque.iter_mut();
+        assert_eq!(iter.len(), 4);
+        *iter.next_back().unwrap() = 1;
+        assert_eq!(values(deque), [104, 1, 103, 1]);
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let mut deque = UDeque::new();
+        fill(&mut deque, 3);
+        deque.pop_front();
+        deque.push_back(3).unwrap();
+        deque.push_back(4).unwrap();
+        let mut iter = deque.into_iter();
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.next_back(), Some(4));
+        assert_eq!(iter.collect::<Vec<_>>(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_drops_every_element() {
+        use std::rc::Rc;
+
+        let counter = Rc::new(());
+        let mut deque = ArrayDeque::<_, 8>::new();
+        for _ in 0..8 {
+            deque.push_front(Rc::clone(&counter)).unwrap();
+        }
+        deque.pop_back();
+        assert_eq!(Rc::strong_count(&counter), 8);
+        let mut iter = deque.into_iter();
+        iter.next();
+        drop(iter);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn test_const_new() {
+        static EMPTY: ArrayDeque<u8, 16> = ArrayDeque::new();
+        assert!(EMPTY.is_empty());
+        assert_eq!(EMPTY.capacity(), 16);
+    }
+}
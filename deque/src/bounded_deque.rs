@@ -0,0 +1,247 @@
+// Copyright (C) 2025 BastIsAwesome (bastisawesomeltd@gmail.com)
+//
+// This file is part of deque.
+//
+// deque is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// deque is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with deque.  If not, see <https://www.gnu.org/licenses/>.
+use crate::deque_array_list::Iter;
+use crate::{Deque, DequeArrayList};
+
+/// What a [`BoundedDeque`] does with a push it has no room for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Hands the pushed element back as an error and leaves the deque as it
+    /// was.
+    Reject,
+    /// Takes the element at the opposite end off to make room, and hands
+    /// that back.
+    OverwriteOldest,
+    /// Panics.
+    Panic,
+}
+
+/// Deque that never holds more than a fixed number of elements. Its buffer
+/// is allocated once, up front, and pushing onto either end of a full deque
+/// does what its [`OverflowPolicy`] says.
+pub struct BoundedDeque<T> {
+    deque: DequeArrayList<T>,
+    capacity: usize,
+    policy: OverflowPolicy,
+}
+
+impl<T> BoundedDeque<T> {
+    pub fn new(capacity: usize, policy: OverflowPolicy) -> Self {
+        Self {
+            deque: DequeArrayList::with_capacity(capacity),
+            capacity,
+            policy,
+        }
+    }
+
+    /// Most elements the deque will hold.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.deque.len() >= self.capacity
+    }
+
+    /// Pushes `element` onto the front. When the deque is full, returns the
+    /// back element under [`OverflowPolicy::OverwriteOldest`], and `element`
+    /// as the error under [`OverflowPolicy::Reject`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the deque is full and the policy is
+    /// [`OverflowPolicy::Panic`].
+    pub fn push_front(&mut self, element: T) -> Result<Option<T>, T> {
+        if !self.is_full() {
+            self.deque.push_front(element);
+            return Ok(None);
+        }
+
+        let element = self.check_overflow(element)?;
+        // With no room at all, the new element is the oldest one as soon as
+        // it is pushed.
+        let Some(evicted) = self.deque.pop_back() else {
+            return Ok(Some(element));
+        };
+        self.deque.push_front(element);
+
+        Ok(Some(evicted))
+    }
+
+    /// Pushes `element` onto the back. When the deque is full, returns the
+    /// front element under [`OverflowPolicy::OverwriteOldest`], and
+    /// `element` as the error under [`OverflowPolicy::Reject`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the deque is full and the policy is
+    /// [`OverflowPolicy::Panic`].
+    pub fn push_back(&mut self, element: T) -> Result<Option<T>, T> {
+        if !self.is_full() {
+            self.deque.push_back(element);
+            return Ok(None);
+        }
+
+        let element = self.check_overflow(element)?;
+        let Some(evicted) = self.deque.pop_front() else {
+            return Ok(Some(element));
+        };
+        self.deque.push_back(element);
+
+        Ok(Some(evicted))
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.deque.pop_front()
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.deque.pop_back()
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.deque.front()
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.deque.back()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.deque.get(index)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deque.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.deque.len()
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.deque.iter()
+    }
+
+    /// The elements as an unbounded deque.
+    pub fn into_inner(self) -> DequeArrayList<T> {
+        self.deque
+    }
+
+    /// Applies the policies that don't overwrite anything to a push onto a
+    /// full deque, passing `element` through under
+    /// [`OverflowPolicy::OverwriteOldest`].
+    fn check_overflow(&self, element: T) -> Result<T, T> {
+        match self.policy {
+            OverflowPolicy::Reject => Err(element),
+            OverflowPolicy::OverwriteOldest => Ok(element),
+            OverflowPolicy::Panic => {
+                panic!("pushed onto a full deque of capacity {}", self.capacity)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn create_full(policy: OverflowPolicy) -> BoundedDeque<usize> {
+        let mut deque = BoundedDeque::new(3, policy);
+        for value in 1..=3 {
+            assert_eq!(deque.push_back(value), Ok(None));
+        }
+        assert!(deque.is_full());
+        deque
+    }
+
+    fn values(deque: BoundedDeque<usize>) -> Vec<usize> {
+        Vec::from(deque.into_inner())
+    }
+
+    #[test]
+    fn test_push_below_capacity() {
+        let mut deque = BoundedDeque::new(3, OverflowPolicy::Panic);
+        assert_eq!(deque.push_front(2), Ok(None));
+        assert_eq!(deque.push_back(3), Ok(None));
+        assert_eq!(deque.push_front(1), Ok(None));
+        assert_eq!(deque.len(), 3);
+        assert_eq!(deque.capacity(), 3);
+        assert_eq!(deque.iter().copied().collect::<Vec<_>>(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_reject() {
+        let mut deque = create_full(OverflowPolicy::Reject);
+        assert_eq!(deque.push_back(4), Err(4));
+        assert_eq!(deque.push_front(0), Err(0));
+        assert_eq!(deque.pop_front(), Some(1));
+        assert_eq!(deque.push_front(0), Ok(None));
+        assert_eq!(values(deque), [0, 2, 3]);
+    }
+
+    #[test]
+    fn test_overwrite_oldest() {
+        let mut deque = create_full(OverflowPolicy::OverwriteOldest);
+        assert_eq!(deque.push_back(4), Ok(Some(1)));
+        assert_eq!(deque.push_back(5), Ok(Some(2)));
+        assert_eq!(deque.push_front(2), Ok(Some(5)));
+        assert_eq!(deque.len(), 3);
+        assert_eq!(deque.front(), Some(&2));
+        assert_eq!(deque.back(), Some(&4));
+        assert_eq!(values(deque), [2, 3, 4]);
+    }
+
+    #[test]
+    fn test_overwrite_oldest_keeps_buffer() {
+        let mut deque = BoundedDeque::new(100, OverflowPolicy::OverwriteOldest);
+        for value in 0..10_000 {
+            deque.push_back(value).unwrap();
+        }
+        assert_eq!(deque.get(0), Some(&9_900));
+        assert_eq!(deque.back(), Some(&9_999));
+        assert_eq!(deque.into_inner().capacity(), 100);
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        let mut deque = BoundedDeque::new(0, OverflowPolicy::OverwriteOldest);
+        assert!(deque.is_full());
+        assert_eq!(deque.push_back(1), Ok(Some(1)));
+        assert_eq!(deque.push_front(2), Ok(Some(2)));
+        assert!(deque.is_empty());
+
+        let mut deque = BoundedDeque::new(0, OverflowPolicy::Reject);
+        assert_eq!(deque.push_front(1), Err(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "full deque")]
+    fn test_panic_back() {
+        create_full(OverflowPolicy::Panic).push_back(4).ok();
+    }
+
+    #[test]
+    #[should_panic(expected = "full deque")]
+    fn test_panic_front() {
+        create_full(OverflowPolicy::Panic).push_front(0).ok();
+    }
+}
@@ -14,136 +14,912 @@
 //
 // You should have received a copy of the GNU General Public License
 // along with deque.  If not, see <https://www.gnu.org/licenses/>.
-use super::{Deque, Node};
+use alloc::collections::TryReserveError;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::iter::FusedIterator;
+use core::mem::{self, MaybeUninit};
+use core::ops::{Index, IndexMut, Range, RangeBounds};
+use core::{ptr, slice};
 
+use super::{index_range, Deque};
+
+/// Smallest buffer allocated once something is pushed.
+const MIN_CAPACITY: usize = 4;
+
+/// Deque stored in a growable ring buffer. Both ends move around the buffer
+/// instead of shifting the elements, and a full buffer doubles, so pushing
+/// and popping at either end is amortised O(1).
 pub struct DequeArrayList<T> {
-    list: Vec<Node<T>>,
+    /// Every slot of the ring. Only the `len` slots from `first` onwards,
+    /// wrapping past the end, hold elements.
+    list: Vec<MaybeUninit<T>>,
+    /// Slot of the front element.
     first: usize,
+    /// Slot just past the back element, where `push_back` writes next.
     last: usize,
+    len: usize,
 }
 
 impl<T> DequeArrayList<T> {
-    fn new() -> Self {
+    pub fn new() -> Self {
         Self {
-            list: vec![],
+            list: Vec::new(),
             first: 0,
             last: 0,
+            len: 0,
         }
     }
 
-    fn from_vec(v: Vec<Node<T>>) -> Self {
-        let last = v.len() - 1;
+    /// Creates a deque that can hold `capacity` elements before it needs to
+    /// grow.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut list = Vec::with_capacity(capacity);
+        list.resize_with(capacity, MaybeUninit::uninit);
 
         Self {
-            list: v,
+            list,
             first: 0,
-            last,
+            last: 0,
+            len: 0,
+        }
+    }
+
+    /// Takes the values in order, reusing the `Vec`'s allocation as the
+    /// buffer.
+    pub fn from_vec(v: Vec<T>) -> Self {
+        let len = v.len();
+        let list: Vec<_> = v.into_iter().map(MaybeUninit::new).collect();
+
+        Self {
+            last: match len == list.len() {
+                true => 0,
+                false => len,
+            },
+            list,
+            first: 0,
+            len,
+        }
+    }
+
+    /// Number of elements the deque can hold without growing.
+    pub fn capacity(&self) -> usize {
+        self.list.len()
+    }
+
+    /// Makes room for at least `additional` more elements, growing the
+    /// buffer to at least double its size if it has to grow at all.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `usize`.
+    pub fn reserve(&mut self, additional: usize) {
+        let needed =
+            self.len.checked_add(additional).expect("capacity overflow");
+        if needed <= self.capacity() {
+            return;
+        }
+
+        let capacity = needed
+            .max(self.capacity().saturating_mul(2))
+            .max(MIN_CAPACITY);
+        self.relocate(Vec::with_capacity(capacity), capacity);
+    }
+
+    /// Like [`DequeArrayList::reserve`], but returns an error instead of
+    /// panicking or aborting when the memory can't be had. The deque is left
+    /// unchanged on error.
+    pub fn try_reserve(
+        &mut self,
+        additional: usize,
+    ) -> Result<(), TryReserveError> {
+        let Some(needed) = self.len.checked_add(additional) else {
+            // The buffer is at least `len` long, so asking it for the same
+            // room overflows too, and produces the error we can't build.
+            return self.list.try_reserve(additional);
+        };
+        if needed <= self.capacity() {
+            return Ok(());
+        }
+
+        let capacity = needed
+            .max(self.capacity().saturating_mul(2))
+            .max(MIN_CAPACITY);
+        let mut list = Vec::new();
+        list.try_reserve_exact(capacity)?;
+        self.relocate(list, capacity);
+
+        Ok(())
+    }
+
+    /// Shrinks the buffer to hold exactly the current elements.
+    pub fn shrink_to_fit(&mut self) {
+        if self.len < self.capacity() {
+            self.relocate(Vec::with_capacity(self.len), self.len);
+        }
+    }
+
+    /// Inserts `element` so it ends up `index` places from the front,
+    /// shifting whichever side of it is shorter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the length.
+    pub fn insert(&mut self, index: usize, element: T) {
+        assert!(index <= self.len, "index out of bounds");
+        self.grow_if_full();
+
+        // Open a free slot at the shorter end and swap it along to `index`.
+        match index < self.len - index {
+            true => {
+                self.first = self.prev_slot(self.first);
+                for index in 0..index {
+                    self.swap_offsets(index, index + 1);
+                }
+            },
+            false => {
+                self.last = self.next_slot(self.last);
+                for index in (index..self.len).rev() {
+                    self.swap_offsets(index + 1, index);
+                }
+            },
+        }
+
+        let slot = self.slot(index);
+        self.list[slot].write(element);
+        self.len += 1;
+    }
+
+    /// Removes and returns the element `index` places from the front,
+    /// shifting whichever side of it is shorter to close the gap.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+
+        let slot = self.slot(index);
+        // SAFETY: the slot of an in-bounds index holds an element, and the
+        // slot is swapped out of the deque below.
+        let element = unsafe { self.list[slot].assume_init_read() };
+        match index < self.len - 1 - index {
+            true => {
+                for index in (0..index).rev() {
+                    self.swap_offsets(index, index + 1);
+                }
+                self.first = self.next_slot(self.first);
+            },
+            false => {
+                for index in index..self.len - 1 {
+                    self.swap_offsets(index, index + 1);
+                }
+                self.last = self.prev_slot(self.last);
+            },
+        }
+        self.len -= 1;
+
+        Some(element)
+    }
+
+    /// Splits off the elements from `at` onwards into a new deque.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at` is greater than the length.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.len, "index out of bounds");
+        let mut other = Self::with_capacity(self.len - at);
+        let (front, back) = self.slots();
+        let (front, back) = match at < front.len() {
+            true => (&front[at..], back),
+            false => (&back[at - front.len()..], &[][..]),
+        };
+        // SAFETY: `other` is empty with room for both runs, and the elements
+        // stop being counted in `self` below.
+        unsafe {
+            other.copy_in(0, front);
+            other.copy_in(front.len(), back);
+        }
+
+        other.len = self.len - at;
+        other.last = other.slot(other.len);
+        self.len = at;
+        self.last = self.slot(at);
+        other
+    }
+
+    /// Moves every element of `other` onto the back, leaving it empty.
+    pub fn append(&mut self, other: &mut Self) {
+        self.reserve(other.len);
+        let (front, back) = other.slots();
+        // SAFETY: the reserve left `other.len` free slots after the back,
+        // and `other` forgets its elements below.
+        unsafe {
+            self.copy_in(self.len, front);
+            self.copy_in(self.len + front.len(), back);
+        }
+
+        self.len += other.len;
+        self.last = self.slot(self.len);
+        other.first = 0;
+        other.last = 0;
+        other.len = 0;
+    }
+
+    /// Removes the elements in `range` and returns them in order. They are
+    /// gone from the deque even if the iterator is dropped early. Dropping
+    /// it shifts the shorter side of the gap to close it, without
+    /// allocating.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range starts after it ends or ends after the length.
+    pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_, T> {
+        let (start, end) = index_range(range, self.len);
+        let len = self.len;
+        // Until the drain is dropped the deque only owns the elements before
+        // the range, so leaking the drain leaks the rest instead of leaving
+        // slots that were read out behind.
+        self.len = start;
+        self.last = self.slot(start);
+
+        Drain {
+            deque: self,
+            start,
+            end,
+            len,
+            remaining: start..end,
+        }
+    }
+
+    /// Keeps only the elements `keep` returns true for, in order.
+    ///
+    /// If `keep` panics, every element is still in the deque and the ones
+    /// kept so far are at the front, in order.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut keep: F) {
+        // Kept elements are swapped down past the rejected ones, which
+        // gather behind them until the end is cut off.
+        let mut kept = 0;
+        for index in 0..self.len {
+            if keep(&self[index]) {
+                self.swap_offsets(kept, index);
+                kept += 1;
+            }
+        }
+        self.truncate(kept);
+    }
+
+    /// Drops elements from the back until at most `len` are left.
+    pub fn truncate(&mut self, len: usize) {
+        while self.len > len {
+            self.pop_back();
+        }
+    }
+
+    /// The elements as two slices, front to back. The second is only
+    /// non-empty when the elements wrap past the end of the buffer.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let (front, back) = self.slots();
+        // SAFETY: `slots` only covers slots that hold elements.
+        unsafe { (assume_init(front), assume_init(back)) }
+    }
+
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let (front, back) = self.slots_mut();
+        // SAFETY: as in `as_slices`.
+        unsafe { (assume_init_mut(front), assume_init_mut(back)) }
+    }
+
+    /// Rearranges the buffer so the elements no longer wrap, and returns
+    /// them as one slice. Doesn't allocate, and does nothing if they
+    /// already sit in one run.
+    pub fn make_contiguous(&mut self) -> &mut [T] {
+        if self.first + self.len <= self.capacity() {
+            return self.as_mut_slices().0;
+        }
+
+        // The front run fills the end of the buffer and the back run its
+        // start, with the free slots between them.
+        let front = self.capacity() - self.first;
+        let back = self.last;
+        let free = self.capacity() - self.len;
+        let list = self.list.as_mut_ptr();
+        // SAFETY: every copy stays within the buffer, and only the copies
+        // that the free slots keep apart are nonoverlapping. Afterwards the
+        // elements sit in order in the slots `first` and `len` describe.
+        unsafe {
+            if free >= front {
+                // Shift the back run up past where the front run will go,
+                // then put the front run at the start.
+                ptr::copy(list, list.add(front), back);
+                ptr::copy_nonoverlapping(list.add(self.first), list, front);
+                self.first = 0;
+            } else if free >= back {
+                // Shift the front run down to just after the back run, then
+                // put the back run after it.
+                ptr::copy(list.add(self.first), list.add(back), front);
+                ptr::copy_nonoverlapping(list, list.add(back + front), back);
+                self.first = back;
+            } else {
+                // Too little room to move either run past the other. Close
+                // the gap, then rotate just the occupied slots.
+                ptr::copy(list.add(self.first), list.add(back), front);
+                self.list[..self.len].rotate_left(back);
+                self.first = 0;
+            }
+        }
+        self.last = self.slot(self.len);
+
+        self.as_mut_slices().0
+    }
+
+    /// Binary searches a deque sorted by `compare`, as
+    /// [`slice::binary_search_by`] does, without making it contiguous
+    /// first.
+    pub fn binary_search_by<F>(&self, mut compare: F) -> Result<usize, usize>
+    where
+        F: FnMut(&T) -> Ordering,
+    {
+        let (front, back) = self.as_slices();
+        match back.first().map(&mut compare) {
+            Some(Ordering::Equal) => Ok(front.len()),
+            Some(Ordering::Less) => {
+                let offset = front.len() + 1;
+                back[1..]
+                    .binary_search_by(compare)
+                    .map(|index| index + offset)
+                    .map_err(|index| index + offset)
+            },
+            _ => front.binary_search_by(compare),
+        }
+    }
+
+    /// Slot `index` places on from the front, wrapping at most once.
+    fn slot(&self, index: usize) -> usize {
+        let slot = self.first + index;
+        match slot >= self.capacity() {
+            true => slot - self.capacity(),
+            false => slot,
+        }
+    }
+
+    /// Swaps the slots `i` and `j` places on from the front, whether or not
+    /// they hold elements.
+    fn swap_offsets(&mut self, i: usize, j: usize) {
+        let (i, j) = (self.slot(i), self.slot(j));
+        self.list.swap(i, j);
+    }
+
+    /// Moves `slot` forward by one, wrapping to the start of the buffer.
+    fn next_slot(&self, slot: usize) -> usize {
+        match slot + 1 == self.capacity() {
+            true => 0,
+            false => slot + 1,
+        }
+    }
+
+    /// Moves `slot` back by one, wrapping to the end of the buffer.
+    fn prev_slot(&self, slot: usize) -> usize {
+        match slot == 0 {
+            true => self.capacity() - 1,
+            false => slot - 1,
+        }
+    }
+
+    /// The slots holding elements, front part first. The second part is
+    /// only non-empty when the elements wrap past the end of the buffer.
+    fn slots(&self) -> (&[MaybeUninit<T>], &[MaybeUninit<T>]) {
+        match self.first + self.len <= self.capacity() {
+            true => (&self.list[self.first..self.first + self.len], &[]),
+            false => (&self.list[self.first..], &self.list[..self.last]),
+        }
+    }
+
+    fn slots_mut(&mut self) -> (&mut [MaybeUninit<T>], &mut [MaybeUninit<T>]) {
+        match self.first + self.len <= self.capacity() {
+            true => {
+                (&mut self.list[self.first..self.first + self.len], &mut [])
+            },
+            false => {
+                let (wrapped, front) = self.list.split_at_mut(self.first);
+                (front, &mut wrapped[..self.last])
+            },
+        }
+    }
+
+    /// Copies `src` into the slots from `index` places on from the front,
+    /// wrapping past the end of the buffer.
+    ///
+    /// # Safety
+    ///
+    /// Those slots must be free, and the elements in `src` must no longer be
+    /// treated as owned where they came from.
+    unsafe fn copy_in(&mut self, index: usize, src: &[MaybeUninit<T>]) {
+        let start = self.slot(index);
+        let (head, tail) = src.split_at(src.len().min(self.capacity() - start));
+        let dst = self.list.as_mut_ptr();
+        // SAFETY: `head` fits between `start` and the end of the buffer and
+        // `tail` at its start, and `src` is borrowed apart from the buffer.
+        unsafe {
+            ptr::copy_nonoverlapping(head.as_ptr(), dst.add(start), head.len());
+            ptr::copy_nonoverlapping(tail.as_ptr(), dst, tail.len());
+        }
+    }
+
+    fn grow_if_full(&mut self) {
+        if self.len == self.capacity() {
+            self.reserve(1);
+        }
+    }
+
+    /// Moves the elements in order to the start of `list`, which has room
+    /// for `capacity` of them, and makes it the buffer.
+    fn relocate(&mut self, mut list: Vec<MaybeUninit<T>>, capacity: usize) {
+        let mut slot = self.first;
+        for _ in 0..self.len {
+            list.push(mem::replace(
+                &mut self.list[slot],
+                MaybeUninit::uninit(),
+            ));
+            slot = self.next_slot(slot);
+        }
+        list.resize_with(capacity, MaybeUninit::uninit);
+
+        self.list = list;
+        self.first = 0;
+        self.last = match self.len == capacity {
+            true => 0,
+            false => self.len,
+        };
+    }
+}
+
+/// # Safety
+///
+/// Every slot must hold an element.
+unsafe fn assume_init<T>(slots: &[MaybeUninit<T>]) -> &[T] {
+    // SAFETY: `MaybeUninit<T>` has the same layout as `T`.
+    unsafe { slice::from_raw_parts(slots.as_ptr().cast(), slots.len()) }
+}
+
+/// # Safety
+///
+/// Every slot must hold an element.
+unsafe fn assume_init_mut<T>(slots: &mut [MaybeUninit<T>]) -> &mut [T] {
+    // SAFETY: as in `assume_init`.
+    unsafe { slice::from_raw_parts_mut(slots.as_mut_ptr().cast(), slots.len()) }
+}
+
+impl<T> Default for DequeArrayList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<Vec<T>> for DequeArrayList<T> {
+    fn from(v: Vec<T>) -> Self {
+        Self::from_vec(v)
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for DequeArrayList<T> {
+    fn from(array: [T; N]) -> Self {
+        Self::from(Vec::from(array))
+    }
+}
+
+/// Collects the values front to back.
+impl<T> From<DequeArrayList<T>> for Vec<T> {
+    fn from(mut deque: DequeArrayList<T>) -> Self {
+        let mut v = Vec::with_capacity(deque.len());
+        while let Some(value) = deque.pop_front() {
+            v.push(value);
+        }
+
+        v
+    }
+}
+
+impl<T> FromIterator<T> for DequeArrayList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut deque = Self::new();
+        deque.extend(iter);
+        deque
+    }
+}
+
+/// Pushes the values onto the back in order.
+impl<T> Extend<T> for DequeArrayList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for value in iter {
+            self.push_back(value);
         }
     }
 }
 
 impl<T> Deque<T> for DequeArrayList<T> {
-    fn push_front(&mut self, element: Node<T>) {
-        self.list.insert(self.first, element);
-        self.last = self.list.len() - 1;
+    type Iter<'a>
+        = Iter<'a, T>
+    where
+        T: 'a;
+    type IterMut<'a>
+        = IterMut<'a, T>
+    where
+        T: 'a;
+
+    fn push_front(&mut self, element: T) {
+        self.grow_if_full();
+        self.first = self.prev_slot(self.first);
+        self.list[self.first].write(element);
+        self.len += 1;
     }
 
-    fn push_back(&mut self, element: Node<T>) {
-        self.list.push(element);
-        self.last = self.list.len() - 1;
+    fn push_back(&mut self, element: T) {
+        self.grow_if_full();
+        self.list[self.last].write(element);
+        self.last = self.next_slot(self.last);
+        self.len += 1;
     }
 
-    fn pop_front(&mut self) -> Option<Node<T>> {
+    fn pop_front(&mut self) -> Option<T> {
         if self.is_empty() {
             return None;
         }
 
-        let elem = self.list.remove(self.first);
-        self.last = self.list.len().checked_sub(1).unwrap_or(0);
+        // SAFETY: the deque isn't empty, so `first` holds an element, and
+        // moving `first` past it leaves nothing else that reads it.
+        let elem = unsafe { self.list[self.first].assume_init_read() };
+        self.first = self.next_slot(self.first);
+        self.len -= 1;
 
         Some(elem)
     }
 
-    fn pop_back(&mut self) -> Option<Node<T>> {
+    fn pop_back(&mut self) -> Option<T> {
         if self.is_empty() {
             return None;
         }
 
-        let elem = self.list.remove(self.last);
-        self.last = self.list.len().checked_sub(1).unwrap_or(0);
+        self.last = self.prev_slot(self.last);
+        self.len -= 1;
+        // SAFETY: as in `pop_front`, for the slot before `last`.
+        let elem = unsafe { self.list[self.last].assume_init_read() };
 
         Some(elem)
     }
 
-    fn front(&self) -> Option<&Node<T>> {
+    fn front(&self) -> Option<&T> {
         match self.is_empty() {
-            true => return None,
-            false => return Some(&self.list[self.first]),
+            true => None,
+            // SAFETY: a non-empty deque holds an element at `first`.
+            false => Some(unsafe { self.list[self.first].assume_init_ref() }),
         }
     }
 
-    fn back(&self) -> Option<&Node<T>> {
+    fn back(&self) -> Option<&T> {
         match self.is_empty() {
-            true => return None,
-            false => return Some(&self.list[self.last]),
+            true => None,
+            // SAFETY: a non-empty deque holds an element just before `last`.
+            false => Some(unsafe {
+                self.list[self.prev_slot(self.last)].assume_init_ref()
+            }),
         }
     }
 
     fn is_empty(&self) -> bool {
-        return self.list.len() == 0;
+        self.len == 0
     }
 
     fn len(&self) -> usize {
-        return self.list.len();
+        self.len
+    }
+
+    fn iter(&self) -> Iter<'_, T> {
+        let (front, back) = self.slots();
+
+        Iter {
+            front: front.iter(),
+            back: back.iter(),
+        }
+    }
+
+    fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let (front, back) = self.slots_mut();
+
+        IterMut {
+            front: front.iter_mut(),
+            back: back.iter_mut(),
+        }
+    }
+
+    fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+
+        // SAFETY: slots of in-bounds indices hold elements.
+        Some(unsafe { self.list[self.slot(index)].assume_init_ref() })
+    }
+
+    fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len {
+            return None;
+        }
+
+        let slot = self.slot(index);
+        // SAFETY: as in `get`.
+        Some(unsafe { self.list[slot].assume_init_mut() })
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        assert!(i < self.len && j < self.len, "index out of bounds");
+        self.swap_offsets(i, j);
+    }
+}
+
+impl<T> Index<usize> for DequeArrayList<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<T> IndexMut<usize> for DequeArrayList<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.get_mut(index).expect("index out of bounds")
+    }
+}
+
+/// The buffer only frees its slots, so the elements still in them are
+/// dropped here.
+impl<T> Drop for DequeArrayList<T> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+impl<T> IntoIterator for DequeArrayList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { deque: self }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a DequeArrayList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut DequeArrayList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+/// Borrows the elements front to back. Walks the two runs of slots the ring
+/// is split into, so it never has to wrap an index.
+pub struct Iter<'a, T> {
+    front: slice::Iter<'a, MaybeUninit<T>>,
+    back: slice::Iter<'a, MaybeUninit<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let slot = self.front.next().or_else(|| self.back.next())?;
+        // SAFETY: the iterator only covers slots that hold elements, and the
+        // deque is borrowed for as long as the references live.
+        Some(unsafe { slot.assume_init_ref() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.front.len() + self.back.len();
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let slot = self.back.next_back().or_else(|| self.front.next_back())?;
+        // SAFETY: as in `next`.
+        Some(unsafe { slot.assume_init_ref() })
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+/// Mutably borrows the elements front to back.
+pub struct IterMut<'a, T> {
+    front: slice::IterMut<'a, MaybeUninit<T>>,
+    back: slice::IterMut<'a, MaybeUninit<T>>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        let slot = self.front.next().or_else(|| self.back.next())?;
+        // SAFETY: as for `Iter`, and every slot is handed out only once.
+        Some(unsafe { slot.assume_init_mut() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.front.len() + self.back.len();
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let slot = self.back.next_back().or_else(|| self.front.next_back())?;
+        // SAFETY: as in `next`.
+        Some(unsafe { slot.assume_init_mut() })
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+/// Moves the elements out front to back. Whatever is left is dropped with
+/// the iterator.
+pub struct IntoIter<T> {
+    deque: DequeArrayList<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.deque.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.deque.len(), Some(self.deque.len()))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.deque.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> FusedIterator for IntoIter<T> {}
+
+/// Moves a range of elements out front to back, borrowing the deque. See
+/// [`DequeArrayList::drain`].
+pub struct Drain<'a, T> {
+    deque: &'a mut DequeArrayList<T>,
+    /// The drained range, as offsets from the front.
+    start: usize,
+    end: usize,
+    /// Length of the deque before the drain.
+    len: usize,
+    /// Offsets not yet moved out.
+    remaining: Range<usize>,
+}
+
+impl<T> Drain<'_, T> {
+    /// Moves the element `index` places from the front out of its slot.
+    ///
+    /// # Safety
+    ///
+    /// `index` must lie in the drained range and not have been read yet.
+    unsafe fn read(&mut self, index: usize) -> T {
+        let slot = self.deque.slot(index);
+        // SAFETY: every slot in the drained range still holds its element
+        // until it is read, which the caller ensures happens once.
+        unsafe { self.deque.list[slot].assume_init_read() }
+    }
+}
+
+impl<T> Iterator for Drain<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let index = self.remaining.next()?;
+        // SAFETY: `remaining` hands out each offset in the range once.
+        Some(unsafe { self.read(index) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.remaining.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for Drain<'_, T> {
+    fn next_back(&mut self) -> Option<T> {
+        let index = self.remaining.next_back()?;
+        // SAFETY: as in `next`.
+        Some(unsafe { self.read(index) })
+    }
+}
+
+impl<T> ExactSizeIterator for Drain<'_, T> {}
+
+impl<T> FusedIterator for Drain<'_, T> {}
+
+impl<T> Drop for Drain<'_, T> {
+    fn drop(&mut self) {
+        // Should one of these panic, the elements after the range stay
+        // leaked rather than being dropped twice.
+        self.for_each(drop);
+
+        let count = self.end - self.start;
+        let deque = &mut *self.deque;
+        match self.start < self.len - self.end {
+            true => {
+                for index in (0..self.start).rev() {
+                    deque.swap_offsets(index, index + count);
+                }
+                deque.first = deque.slot(count);
+            },
+            false => {
+                for index in self.end..self.len {
+                    deque.swap_offsets(index - count, index);
+                }
+            },
+        }
+        deque.len = self.len - count;
+        deque.last = deque.slot(deque.len);
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod test {
     use super::DequeArrayList;
     use crate::{Deque, Node};
 
-    type UNode = Node<usize>;
     type UDeque = DequeArrayList<usize>;
 
     #[test]
     fn test_push_front_empty() {
         let mut deque = UDeque::new();
-        deque.push_front(UNode::from(255));
-        assert_eq!(deque.back().unwrap().data, Some(255));
-        assert_eq!(deque.front().unwrap().data, Some(255));
+        deque.push_front(255);
+        assert_eq!(deque.back(), Some(&255));
+        assert_eq!(deque.front(), Some(&255));
     }
 
     #[test]
     fn test_push_front_filled() {
-        let mut deque = UDeque::from_vec(vec![
-            UNode::from(1),
-            UNode::from(2),
-            UNode::from(3),
-        ]);
-        deque.push_front(UNode::from(255));
-        assert_eq!(deque.front().unwrap().data, Some(255));
-        assert_eq!(deque.back().unwrap().data, Some(3))
+        let mut deque = UDeque::from_vec(vec![1, 2, 3]);
+        deque.push_front(255);
+        assert_eq!(deque.front(), Some(&255));
+        assert_eq!(deque.back(), Some(&3))
     }
 
     #[test]
     fn test_push_back_empty() {
         let mut deque = UDeque::new();
-        deque.push_back(UNode::from(255));
-        assert_eq!(deque.back().unwrap().data, Some(255));
-        assert_eq!(deque.front().unwrap().data, Some(255));
+        deque.push_back(255);
+        assert_eq!(deque.back(), Some(&255));
+        assert_eq!(deque.front(), Some(&255));
     }
 
     #[test]
     fn test_push_back_filled() {
-        let mut deque = UDeque::from_vec(vec![
-            UNode::from(1),
-            UNode::from(2),
-            UNode::from(3),
-        ]);
-        deque.push_back(UNode::from(255));
-        assert_eq!(deque.front().unwrap().data, Some(1));
-        assert_eq!(deque.back().unwrap().data, Some(255))
+        let mut deque = UDeque::from_vec(vec![1, 2, 3]);
+        deque.push_back(255);
+        assert_eq!(deque.front(), Some(&1));
+        assert_eq!(deque.back(), Some(&255))
     }
 
     #[test]
@@ -157,24 +933,20 @@ mod test {
 
     #[test]
     fn test_pop_front_one_element() {
-        let mut deque = UDeque::from_vec(vec![UNode::from(1)]);
+        let mut deque = UDeque::from_vec(vec![1]);
         let elem = deque.pop_front();
-        assert_eq!(elem.unwrap().data, Some(1));
+        assert_eq!(elem, Some(1));
         assert_eq!(deque.front().is_none(), true);
         assert_eq!(deque.back().is_none(), true);
     }
 
     #[test]
     fn test_pop_front_filled() {
-        let mut deque = UDeque::from_vec(vec![
-            UNode::from(1),
-            UNode::from(2),
-            UNode::from(3),
-        ]);
+        let mut deque = UDeque::from_vec(vec![1, 2, 3]);
         let elem = deque.pop_front();
-        assert_eq!(elem.unwrap().data, Some(1));
-        assert_eq!(deque.front().unwrap().data, Some(2));
-        assert_eq!(deque.back().unwrap().data, Some(3));
+        assert_eq!(elem, Some(1));
+        assert_eq!(deque.front(), Some(&2));
+        assert_eq!(deque.back(), Some(&3));
     }
 
     #[test]
@@ -188,24 +960,20 @@ mod test {
 
     #[test]
     fn test_pop_back_one_element() {
-        let mut deque = UDeque::from_vec(vec![UNode::from(1)]);
+        let mut deque = UDeque::from_vec(vec![1]);
         let elem = deque.pop_back();
-        assert_eq!(elem.unwrap().data, Some(1));
+        assert_eq!(elem, Some(1));
         assert_eq!(deque.front().is_none(), true);
         assert_eq!(deque.back().is_none(), true);
     }
 
     #[test]
     fn test_pop_back_filled() {
-        let mut deque = UDeque::from_vec(vec![
-            UNode::from(1),
-            UNode::from(2),
-            UNode::from(3),
-        ]);
+        let mut deque = UDeque::from_vec(vec![1, 2, 3]);
         let elem = deque.pop_back();
-        assert_eq!(elem.unwrap().data, Some(3));
-        assert_eq!(deque.front().unwrap().data, Some(1));
-        assert_eq!(deque.back().unwrap().data, Some(2));
+        assert_eq!(elem, Some(3));
+        assert_eq!(deque.front(), Some(&1));
+        assert_eq!(deque.back(), Some(&2));
     }
 
     #[test]
@@ -216,12 +984,8 @@ mod test {
 
     #[test]
     fn test_front_filled() {
-        let deque = UDeque::from_vec(vec![
-            UNode::from(1),
-            UNode::from(2),
-            UNode::from(3),
-        ]);
-        assert_eq!(deque.front().unwrap().data, Some(1));
+        let deque = UDeque::from_vec(vec![1, 2, 3]);
+        assert_eq!(deque.front(), Some(&1));
     }
 
     #[test]
@@ -232,12 +996,8 @@ mod test {
 
     #[test]
     fn test_back_filled() {
-        let deque = UDeque::from_vec(vec![
-            UNode::from(1),
-            UNode::from(2),
-            UNode::from(3),
-        ]);
-        assert_eq!(deque.back().unwrap().data, Some(3));
+        let deque = UDeque::from_vec(vec![1, 2, 3]);
+        assert_eq!(deque.back(), Some(&3));
     }
 
     #[test]
@@ -248,8 +1008,7 @@ mod test {
 
     #[test]
     fn test_empty_filled() {
-        let deque =
-            UDeque::from_vec(vec![Node::from(1), Node::from(2), Node::from(3)]);
+        let deque = UDeque::from_vec(vec![1, 2, 3]);
         assert_eq!(deque.is_empty(), false);
     }
 
@@ -261,11 +1020,639 @@ mod test {
 
     #[test]
     fn test_size_filled() {
-        let deque = UDeque::from_vec(vec![
-            UNode::from(1),
-            UNode::from(2),
-            UNode::from(3),
-        ]);
+        let deque = UDeque::from_vec(vec![1, 2, 3]);
         assert_eq!(deque.len(), 3);
     }
+
+    #[test]
+    fn test_push_front_wraps() {
+        let mut deque = UDeque::new();
+        for value in 0..100 {
+            deque.push_front(value);
+        }
+        assert_eq!(deque.len(), 100);
+        assert_eq!(deque.front(), Some(&99));
+        assert_eq!(deque.back(), Some(&0));
+        for value in (0..100).rev() {
+            assert_eq!(deque.pop_front(), Some(value));
+        }
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn test_grow_while_wrapped() {
+        let mut deque = UDeque::from_vec(vec![2, 3]);
+        deque.push_front(1);
+        deque.push_front(0);
+        // Full with the front wrapped to the end of the buffer.
+        assert_eq!(deque.capacity(), 4);
+        deque.push_back(4);
+        assert_eq!(deque.capacity(), 8);
+        for value in 0..5 {
+            assert_eq!(deque.pop_front(), Some(value));
+        }
+        assert!(deque.pop_back().is_none());
+    }
+
+    #[test]
+    fn test_capacity_doubles() {
+        let mut deque = UDeque::new();
+        assert_eq!(deque.capacity(), 0);
+        for value in 0..1000 {
+            deque.push_back(value);
+        }
+        assert_eq!(deque.capacity(), 1024);
+    }
+
+    #[test]
+    fn test_matches_vec_deque() {
+        use std::collections::VecDeque;
+
+        let mut deque = UDeque::new();
+        let mut expected = VecDeque::new();
+        // Deterministic mix of operations that keeps the ends wrapping.
+        let mut state: usize = 17;
+        for _ in 0..10_000 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            let value = state >> 33;
+            match value % 5 {
+                0 | 1 => {
+                    deque.push_front(value);
+                    expected.push_front(value);
+                },
+                2 => {
+                    deque.push_back(value);
+                    expected.push_back(value);
+                },
+                3 => {
+                    let elem = deque.pop_front();
+                    assert_eq!(elem, expected.pop_front());
+                },
+                _ => {
+                    let elem = deque.pop_back();
+                    assert_eq!(elem, expected.pop_back());
+                },
+            }
+
+            assert_eq!(deque.len(), expected.len());
+            assert_eq!(deque.front().copied(), expected.front().copied());
+            assert_eq!(deque.back().copied(), expected.back().copied());
+        }
+    }
+
+    #[test]
+    fn test_drops_every_element() {
+        use std::rc::Rc;
+
+        let counter = Rc::new(());
+        let mut deque = DequeArrayList::new();
+        for _ in 0..10 {
+            deque.push_front(Rc::clone(&counter));
+        }
+        deque.pop_back();
+        assert_eq!(Rc::strong_count(&counter), 10);
+        drop(deque);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn test_from_vec_empty() {
+        let mut deque = UDeque::from_vec(vec![]);
+        assert!(deque.is_empty());
+        assert!(deque.front().is_none());
+        deque.push_front(1);
+        assert_eq!(deque.back(), Some(&1));
+    }
+
+    #[test]
+    fn test_from_vec_then_push() {
+        let mut deque = UDeque::from_vec(vec![1, 2]);
+        deque.push_back(3);
+        deque.push_front(0);
+        assert_eq!(Vec::from(deque), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_with_capacity() {
+        let mut deque = UDeque::with_capacity(10);
+        assert_eq!(deque.capacity(), 10);
+        assert!(deque.is_empty());
+        for value in 0..10 {
+            deque.push_front(value);
+        }
+        assert_eq!(deque.capacity(), 10);
+        deque.push_front(10);
+        assert_eq!(deque.capacity(), 20);
+    }
+
+    #[test]
+    fn test_conversions() {
+        let deque = UDeque::from(vec![1, 2, 3]);
+        assert_eq!(deque.front(), Some(&1));
+        assert_eq!(deque.back(), Some(&3));
+
+        let deque = UDeque::from([4, 5]);
+        assert_eq!(deque.len(), 2);
+
+        let mut deque: UDeque = (0..5).collect();
+        deque.extend([5, 6]);
+        deque.push_front(255);
+        let v: Vec<usize> = deque.into();
+        assert_eq!(v, [255, 0, 1, 2, 3, 4, 5, 6]);
+
+        assert!(Vec::from(UDeque::new()).is_empty());
+    }
+
+    #[test]
+    fn test_reserve() {
+        let mut deque = UDeque::from([1, 2, 3]);
+        deque.pop_front();
+        deque.push_back(4);
+        deque.reserve(2);
+        assert!(deque.capacity() >= 5);
+        deque.reserve(0);
+        assert_eq!(Vec::from(deque), [2, 3, 4]);
+    }
+
+    #[test]
+    fn test_try_reserve() {
+        let mut deque = UDeque::from([1, 2, 3]);
+        assert!(deque.try_reserve(10).is_ok());
+        assert!(deque.capacity() >= 13);
+        assert!(deque.try_reserve(usize::MAX).is_err());
+        assert!(deque.try_reserve(usize::MAX / 2).is_err());
+        assert_eq!(Vec::from(deque), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_shrink_to_fit() {
+        let mut deque = UDeque::with_capacity(100);
+        deque.extend([2, 3]);
+        deque.push_front(1);
+        deque.shrink_to_fit();
+        assert_eq!(deque.capacity(), 3);
+        deque.push_back(4);
+        assert_eq!(Vec::from(deque), [1, 2, 3, 4]);
+
+        let mut deque = UDeque::with_capacity(8);
+        deque.shrink_to_fit();
+        assert_eq!(deque.capacity(), 0);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_node_elements() {
+        let mut deque = crate::NodeDequeArrayList::new();
+        deque.push_back(Node::from(1));
+        deque.push_front(Node::new());
+        assert!(deque.front().unwrap().is_empty());
+        assert_eq!(deque.back().unwrap().data, Some(1));
+        let values: Vec<Option<usize>> =
+            Vec::from(deque).into_iter().map(Option::from).collect();
+        assert_eq!(values, [None, Some(1)]);
+    }
+
+    /// Sums any deque through the trait alone.
+    fn sum<D: Deque<usize>>(deque: &D) -> usize {
+        deque.iter().sum()
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut deque = UDeque::with_capacity(4);
+        deque.extend([2, 3, 4]);
+        deque.pop_front();
+        deque.push_back(5);
+        deque.push_front(1);
+        // The elements now wrap past the end of the buffer.
+        let mut iter = deque.iter();
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&5));
+        assert_eq!(iter.next_back(), Some(&4));
+        assert_eq!(iter.len(), 1);
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        assert_eq!(deque.iter().rev().collect::<Vec<_>>(), [&5, &4, &3, &1]);
+        assert_eq!(sum(&deque), 13);
+        assert_eq!(UDeque::new().iter().next(), None);
+    }
+
+    #[test]
+    fn test_iter_mut() {
+        let mut deque = UDeque::from([1, 2, 3]);
+        deque.push_front(0);
+        for value in deque.iter_mut() {
+            *value *= 10;
+        }
+        if let Some(value) = deque.iter_mut().next_back() {
+            *value += 1;
+        }
+        for value in &mut deque {
+            *value += 1;
+        }
+        assert_eq!(deque.iter_mut().len(), 4);
+        assert_eq!(Vec::from(deque), [1, 11, 21, 32]);
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let mut deque = UDeque::from([2, 3]);
+        deque.push_front(1);
+        let mut iter = deque.into_iter();
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next_back(), Some(3));
+        assert_eq!(iter.collect::<Vec<_>>(), [1, 2]);
+
+        let mut values = vec![];
+        for value in UDeque::from([4, 5]) {
+            values.push(value);
+        }
+        assert_eq!(values, [4, 5]);
+    }
+
+    #[test]
+    fn test_into_iter_drops_rest() {
+        use std::rc::Rc;
+
+        let counter = Rc::new(());
+        let deque: DequeArrayList<_> =
+            (0..5).map(|_| Rc::clone(&counter)).collect();
+        let mut iter = deque.into_iter();
+        iter.next();
+        assert_eq!(Rc::strong_count(&counter), 5);
+        drop(iter);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn test_get() {
+        let mut deque = UDeque::with_capacity(4);
+        deque.extend([3, 4]);
+        deque.push_front(2);
+        deque.push_front(1);
+        deque.pop_back();
+        deque.push_back(5);
+        // 1 and 2 sit at the end of the buffer, 3 and 5 at the start.
+        assert_eq!(deque.get(0), Some(&1));
+        assert_eq!(deque.get(2), Some(&3));
+        assert_eq!(deque.get(3), Some(&5));
+        assert_eq!(deque.get(4), None);
+        assert_eq!(deque[1], 2);
+
+        *deque.get_mut(3).unwrap() = 4;
+        deque[0] = 0;
+        assert!(deque.get_mut(4).is_none());
+        assert_eq!(Vec::from(deque), [0, 2, 3, 4]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_out_of_bounds() {
+        let deque = UDeque::from([1, 2, 3]);
+        let _ = deque[3];
+    }
+
+    #[test]
+    fn test_swap() {
+        let mut deque = UDeque::from([2, 3, 4]);
+        deque.push_front(1);
+        deque.swap(0, 3);
+        deque.swap(2, 1);
+        deque.swap(1, 1);
+        assert_eq!(Vec::from(deque), [4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn test_rotate() {
+        let mut deque: UDeque = (0..10).collect();
+        deque.rotate_left(3);
+        assert_eq!(
+            deque.iter().copied().collect::<Vec<_>>(),
+            [3, 4, 5, 6, 7, 8, 9, 0, 1, 2]
+        );
+        deque.rotate_left(8);
+        assert_eq!(deque.front(), Some(&1));
+        deque.rotate_right(1);
+        deque.rotate_left(0);
+        deque.rotate_right(10);
+        assert_eq!(Vec::from(deque), (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_rotate_too_far() {
+        UDeque::from([1, 2]).rotate_right(3);
+    }
+
+    /// Builds `first..last` so the front wraps past the end of the buffer.
+    fn create_wrapped(first: usize, last: usize) -> UDeque {
+        let mut deque = UDeque::with_capacity(16);
+        deque.extend(first + 4..last);
+        for value in (first..first + 4).rev() {
+            deque.push_front(value);
+        }
+        deque
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut deque = create_wrapped(0, 10);
+        deque.insert(2, 100);
+        deque.insert(9, 200);
+        deque.insert(0, 300);
+        deque.insert(13, 400);
+        assert_eq!(
+            Vec::from(deque),
+            [300, 0, 1, 100, 2, 3, 4, 5, 6, 7, 200, 8, 9, 400]
+        );
+
+        let mut deque = UDeque::new();
+        deque.insert(0, 1);
+        deque.insert(0, 0);
+        assert_eq!(Vec::from(deque), [0, 1]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_insert_out_of_bounds() {
+        UDeque::from([1]).insert(2, 0);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut deque = create_wrapped(0, 10);
+        assert_eq!(deque.remove(2), Some(2));
+        assert_eq!(deque.remove(6), Some(7));
+        assert_eq!(deque.remove(0), Some(0));
+        assert_eq!(deque.remove(6), Some(9));
+        assert_eq!(deque.remove(6), None);
+        deque.push_front(100);
+        deque.push_back(200);
+        assert_eq!(Vec::from(deque), [100, 1, 3, 4, 5, 6, 8, 200]);
+    }
+
+    #[test]
+    fn test_split_off_append() {
+        let mut deque = create_wrapped(0, 10);
+        let mut back = deque.split_off(6);
+        assert_eq!(deque.len(), 6);
+        assert_eq!(back.iter().copied().collect::<Vec<_>>(), [6, 7, 8, 9]);
+        assert!(deque.split_off(6).is_empty());
+
+        back.append(&mut deque);
+        assert!(deque.is_empty());
+        assert_eq!(Vec::from(back), [6, 7, 8, 9, 0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut deque = create_wrapped(0, 10);
+        assert_eq!(deque.drain(1..3).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(deque.drain(5..=6).collect::<Vec<_>>(), [7, 8]);
+        assert_eq!(deque.drain(2..2).len(), 0);
+        deque.push_front(100);
+        assert_eq!(Vec::from(deque), [100, 0, 3, 4, 5, 6, 9]);
+
+        let mut deque = create_wrapped(0, 10);
+        drop(deque.drain(..4));
+        assert_eq!(deque.drain(3..).collect::<Vec<_>>(), [7, 8, 9]);
+        deque.push_back(100);
+        assert_eq!(Vec::from(deque), [4, 5, 6, 100]);
+
+        let mut deque = create_wrapped(0, 10);
+        assert_eq!(deque.drain(..).len(), 10);
+        assert!(deque.is_empty());
+        deque.push_back(1);
+        assert_eq!(deque.front(), Some(&1));
+    }
+
+    #[test]
+    fn test_drain_in_place() {
+        let mut deque = create_wrapped(0, 10);
+        let capacity = deque.capacity();
+        let mut drain = deque.drain(2..8);
+        assert_eq!(drain.next(), Some(2));
+        assert_eq!(drain.next_back(), Some(7));
+        assert_eq!(drain.len(), 4);
+        drop(drain);
+        assert_eq!(deque.capacity(), capacity);
+        assert_eq!(deque.iter().copied().collect::<Vec<_>>(), [0, 1, 8, 9]);
+        deque.push_front(100);
+        deque.push_back(200);
+        assert_eq!(Vec::from(deque), [100, 0, 1, 8, 9, 200]);
+    }
+
+    #[test]
+    fn test_drain_drops_once() {
+        let value = std::rc::Rc::new(());
+        let mut deque = DequeArrayList::new();
+        deque.extend((0..10).map(|_| value.clone()));
+        let mut drain = deque.drain(1..9);
+        drop(drain.next());
+        drop(drain.next_back());
+        assert_eq!(std::rc::Rc::strong_count(&value), 9);
+        drop(drain);
+        assert_eq!(std::rc::Rc::strong_count(&value), 3);
+        drop(deque);
+        assert_eq!(std::rc::Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn test_drain_forgotten() {
+        // Leaking the drain leaks everything from the range on, but leaves
+        // the front of the deque usable.
+        let mut deque = create_wrapped(0, 10);
+        std::mem::forget(deque.drain(3..5));
+        assert_eq!(deque.len(), 3);
+        deque.push_back(100);
+        assert_eq!(Vec::from(deque), [0, 1, 2, 100]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_drain_out_of_bounds() {
+        create_wrapped(0, 10).drain(5..11);
+    }
+
+    #[test]
+    fn test_retain_truncate() {
+        let mut deque = create_wrapped(0, 10);
+        deque.retain(|value| value % 3 != 0);
+        assert_eq!(
+            deque.iter().copied().collect::<Vec<_>>(),
+            [1, 2, 4, 5, 7, 8]
+        );
+        deque.truncate(10);
+        assert_eq!(deque.len(), 6);
+        deque.truncate(4);
+        assert_eq!(Vec::from(deque), [1, 2, 4, 5]);
+    }
+
+    #[test]
+    fn test_retain_panic() {
+        use std::panic::{self, AssertUnwindSafe};
+
+        let mut deque = create_wrapped(0, 10);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            deque.retain(|value| {
+                assert_ne!(*value, 5);
+                value % 2 == 0
+            })
+        }));
+        assert!(result.is_err());
+
+        let mut values: Vec<_> = deque.iter().copied().collect();
+        assert_eq!(values[..3], [0, 2, 4]);
+        assert_eq!(values[5..], [5, 6, 7, 8, 9]);
+        values.sort();
+        assert_eq!(values, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_split_off_append_wrapped() {
+        // Split inside the wrapped front run.
+        let mut deque = create_wrapped(0, 10);
+        let mut back = deque.split_off(2);
+        assert_eq!(deque.iter().copied().collect::<Vec<_>>(), [0, 1]);
+        assert_eq!(
+            back.iter().copied().collect::<Vec<_>>(),
+            [2, 3, 4, 5, 6, 7, 8, 9]
+        );
+
+        // Append into free slots that wrap past the end of the buffer.
+        let mut front = UDeque::with_capacity(16);
+        front.extend(0..12);
+        for _ in 0..10 {
+            front.pop_front();
+        }
+        front.append(&mut back);
+        assert!(back.is_empty());
+        assert_eq!(front.capacity(), 16);
+        assert_eq!(Vec::from(front), [10, 11, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+        back.push_back(1);
+        assert_eq!(Vec::from(back), [1]);
+    }
+
+    #[test]
+    fn test_editing_matches_vec() {
+        let mut deque = UDeque::new();
+        let mut expected = vec![];
+        let mut state: usize = 5;
+        for step in 0..2_000 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            let value = state >> 33;
+            let index = value % (expected.len() + 1);
+            match value % 4 {
+                0 => {
+                    deque.push_front(step);
+                    expected.insert(0, step);
+                },
+                1 | 2 => {
+                    deque.insert(index, step);
+                    expected.insert(index, step);
+                },
+                _ => {
+                    let removed = (index < expected.len())
+                        .then(|| expected.remove(index));
+                    assert_eq!(deque.remove(index), removed);
+                },
+            }
+        }
+        assert_eq!(Vec::from(deque), expected);
+    }
+
+    #[test]
+    fn test_as_slices() {
+        let mut deque = create_wrapped(0, 10);
+        let (front, back) = deque.as_slices();
+        assert_eq!(front, [0, 1, 2, 3]);
+        assert_eq!(back, [4, 5, 6, 7, 8, 9]);
+
+        let (front, back) = deque.as_mut_slices();
+        front[0] = 10;
+        back[5] = 90;
+        assert_eq!(deque.front(), Some(&10));
+        assert_eq!(deque.back(), Some(&90));
+
+        let deque = UDeque::from([1, 2]);
+        assert_eq!(deque.as_slices(), (&[1, 2][..], &[][..]));
+        assert_eq!(UDeque::new().as_slices(), (&[][..], &[][..]));
+    }
+
+    #[test]
+    fn test_make_contiguous() {
+        let mut deque = create_wrapped(0, 10);
+        deque.push_back(10);
+        let capacity = deque.capacity();
+        deque.make_contiguous().reverse();
+        assert_eq!(deque.capacity(), capacity);
+        assert_eq!(deque.as_slices().1, []);
+        assert_eq!(deque.as_slices().0, [10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0]);
+
+        deque.push_front(11);
+        deque.push_back(12);
+        assert_eq!(deque.make_contiguous().len(), 13);
+        assert_eq!(deque.front(), Some(&11));
+        assert_eq!(deque.back(), Some(&12));
+
+        // A full buffer wraps with no free slot to spare.
+        let mut deque = UDeque::with_capacity(4);
+        deque.extend([2, 3]);
+        deque.push_front(1);
+        deque.push_front(0);
+        assert_eq!(deque.make_contiguous(), [0, 1, 2, 3]);
+        deque.push_back(4);
+        assert_eq!(Vec::from(deque), [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_make_contiguous_long_front() {
+        // The free slots only fit the back run.
+        let mut deque = UDeque::with_capacity(16);
+        deque.extend([10, 11]);
+        for value in (0..10).rev() {
+            deque.push_front(value);
+        }
+        assert_eq!(deque.make_contiguous(), (0..12).collect::<Vec<_>>());
+        assert_eq!(deque.capacity(), 16);
+        deque.push_back(12);
+        deque.push_front(100);
+        assert_eq!(deque.front(), Some(&100));
+        assert_eq!(deque.back(), Some(&12));
+
+        // The free slots fit neither run.
+        let mut deque = UDeque::with_capacity(16);
+        deque.extend(7..14);
+        for value in (0..7).rev() {
+            deque.push_front(value);
+        }
+        assert_eq!(deque.make_contiguous(), (0..14).collect::<Vec<_>>());
+        deque.push_back(14);
+        deque.push_back(15);
+        assert_eq!(Vec::from(deque), (0..16).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_binary_search_by() {
+        let deque = create_wrapped(0, 10);
+        for value in 0..10 {
+            assert_eq!(deque.binary_search_by(|x| x.cmp(&value)), Ok(value));
+        }
+
+        let deque: UDeque =
+            create_wrapped(0, 10).iter().map(|x| x * 2).collect();
+        let mut deque_wrapped = create_wrapped(0, 10);
+        deque_wrapped.iter_mut().for_each(|x| *x *= 2);
+        for deque in [deque, deque_wrapped] {
+            assert_eq!(deque.binary_search_by(|x| x.cmp(&7)), Err(4));
+            assert_eq!(deque.binary_search_by(|x| x.cmp(&9)), Err(5));
+            assert_eq!(deque.binary_search_by(|x| x.cmp(&100)), Err(10));
+            assert_eq!(deque.binary_search_by(|x| x.cmp(&0)), Ok(0));
+        }
+        assert_eq!(UDeque::new().binary_search_by(|x| x.cmp(&1)), Err(0));
+    }
 }
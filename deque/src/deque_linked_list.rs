@@ -14,8 +14,1009 @@
 //
 // You should have received a copy of the GNU General Public License
 // along with deque.  If not, see <https://www.gnu.org/licenses/>.
-use super::Deque;
+use alloc::boxed::Box;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::RangeBounds;
+use core::ptr::NonNull;
 
-pub struct DequeLinkedList {}
+use super::{index_range, Deque};
 
-impl DequeLinkedList {}
+/// Doubly linked deque. Each element gets its own heap allocation and stays
+/// at that address until it is popped, however the list around it changes.
+pub struct DequeLinkedList<T> {
+    head: Option<NonNull<ListNode<T>>>,
+    tail: Option<NonNull<ListNode<T>>>,
+    len: usize,
+    /// The list owns its nodes, which the drop checker can't see through the
+    /// raw pointers.
+    marker: PhantomData<Box<ListNode<T>>>,
+}
+
+struct ListNode<T> {
+    element: T,
+    prev: Option<NonNull<ListNode<T>>>,
+    next: Option<NonNull<ListNode<T>>>,
+}
+
+impl<T> DequeLinkedList<T> {
+    pub fn new() -> Self {
+        Self {
+            head: None,
+            tail: None,
+            len: 0,
+            marker: PhantomData,
+        }
+    }
+
+    #[cfg(test)]
+    fn from_vec(v: alloc::vec::Vec<T>) -> Self {
+        let mut deque = Self::new();
+        for element in v {
+            deque.push_back(element);
+        }
+
+        deque
+    }
+
+    /// Inserts `element` so it ends up `index` places from the front.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the length.
+    pub fn insert(&mut self, index: usize, element: T) {
+        assert!(index <= self.len, "index out of bounds");
+        if index == self.len {
+            return self.push_back(element);
+        }
+
+        let mut next = self.node_at(index);
+        // SAFETY: `next` and the node before it, if any, are live and owned
+        // by this list.
+        unsafe {
+            let prev = next.as_ref().prev;
+            let node = Self::allocate(element, prev, Some(next));
+            next.as_mut().prev = Some(node);
+            match prev {
+                Some(mut prev) => prev.as_mut().next = Some(node),
+                None => self.head = Some(node),
+            }
+        }
+        self.len += 1;
+    }
+
+    /// Removes and returns the element `index` places from the front.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+
+        let node = self.node_at(index);
+        // SAFETY: `node_at` returns a node of this list.
+        Some(unsafe { self.detach(node) })
+    }
+
+    /// Splits off the elements from `at` onwards into a new list by cutting
+    /// one pair of links, so no element moves.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at` is greater than the length.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.len, "index out of bounds");
+        if at == 0 {
+            return mem::take(self);
+        }
+        if at == self.len {
+            return Self::new();
+        }
+
+        let mut head = self.node_at(at);
+        let tail = self.tail;
+        // SAFETY: `at` isn't 0, so `head` isn't the first node and has one
+        // before it. Both are live and owned by this list.
+        unsafe {
+            let mut back = head.as_ref().prev.unwrap();
+            back.as_mut().next = None;
+            head.as_mut().prev = None;
+            self.tail = Some(back);
+        }
+
+        let other = Self {
+            head: Some(head),
+            tail,
+            len: self.len - at,
+            marker: PhantomData,
+        };
+        self.len = at;
+        other
+    }
+
+    /// Moves every element of `other` onto the back in O(1), leaving it
+    /// empty.
+    pub fn append(&mut self, other: &mut Self) {
+        let Some(mut other_head) = other.head else {
+            return;
+        };
+        let Some(mut tail) = self.tail else {
+            return mem::swap(self, other);
+        };
+
+        // SAFETY: both nodes are live, and from here on `self` owns them all.
+        unsafe {
+            tail.as_mut().next = Some(other_head);
+            other_head.as_mut().prev = Some(tail);
+        }
+        self.tail = other.tail.take();
+        self.len += other.len;
+        other.head = None;
+        other.len = 0;
+    }
+
+    /// Removes the elements in `range` and returns them in order. They are
+    /// gone from the list even if the iterator is dropped early.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range starts after it ends or ends after the length.
+    pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> IntoIter<T> {
+        let (start, end) = index_range(range, self.len);
+        let mut back = self.split_off(end);
+        let drained = self.split_off(start);
+        self.append(&mut back);
+
+        drained.into_iter()
+    }
+
+    /// Keeps only the elements `keep` returns true for, in order.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut keep: F) {
+        let mut cursor = self.head;
+        while let Some(node) = cursor {
+            // SAFETY: `cursor` only ever points at nodes still in the list,
+            // and is moved on before the node it was at can be freed.
+            unsafe {
+                cursor = node.as_ref().next;
+                if !keep(&node.as_ref().element) {
+                    self.detach(node);
+                }
+            }
+        }
+    }
+
+    /// Drops elements from the back until at most `len` are left.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            drop(self.split_off(len));
+        }
+    }
+
+    /// Hands a new node to the list, which frees it again in
+    /// [`DequeLinkedList::unlink`].
+    fn allocate(
+        element: T,
+        prev: Option<NonNull<ListNode<T>>>,
+        next: Option<NonNull<ListNode<T>>>,
+    ) -> NonNull<ListNode<T>> {
+        let node = Box::new(ListNode {
+            element,
+            prev,
+            next,
+        });
+
+        NonNull::from(Box::leak(node))
+    }
+
+    /// Takes back ownership of a node that has just been detached from the
+    /// list.
+    ///
+    /// # Safety
+    ///
+    /// `node` must have come from [`DequeLinkedList::allocate`] and no
+    /// longer be reachable from the list.
+    unsafe fn unlink(node: NonNull<ListNode<T>>) -> T {
+        let node = unsafe { Box::from_raw(node.as_ptr()) };
+        node.element
+    }
+
+    /// Links the neighbours of `node` to each other and frees it.
+    ///
+    /// # Safety
+    ///
+    /// `node` must be one of this list's nodes.
+    unsafe fn detach(&mut self, node: NonNull<ListNode<T>>) -> T {
+        unsafe {
+            let (prev, next) = (node.as_ref().prev, node.as_ref().next);
+            match prev {
+                Some(mut prev) => prev.as_mut().next = next,
+                None => self.head = next,
+            }
+            match next {
+                Some(mut next) => next.as_mut().prev = prev,
+                None => self.tail = prev,
+            }
+
+            self.len -= 1;
+            Self::unlink(node)
+        }
+    }
+
+    /// Node `index` places from the front, which must be in bounds, reached
+    /// from whichever end is closer.
+    fn node_at(&self, index: usize) -> NonNull<ListNode<T>> {
+        // SAFETY: every node linked from the list is live, and an in-bounds
+        // index never walks off either end.
+        unsafe {
+            match index < self.len / 2 {
+                true => {
+                    let mut node = self.head.unwrap();
+                    for _ in 0..index {
+                        node = node.as_ref().next.unwrap();
+                    }
+                    node
+                },
+                false => {
+                    let mut node = self.tail.unwrap();
+                    for _ in index + 1..self.len {
+                        node = node.as_ref().prev.unwrap();
+                    }
+                    node
+                },
+            }
+        }
+    }
+}
+
+impl<T> Default for DequeLinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Deque<T> for DequeLinkedList<T> {
+    type Iter<'a>
+        = Iter<'a, T>
+    where
+        T: 'a;
+    type IterMut<'a>
+        = IterMut<'a, T>
+    where
+        T: 'a;
+
+    fn push_front(&mut self, element: T) {
+        let node = Self::allocate(element, None, self.head);
+        match self.head {
+            // SAFETY: `head` points to a live node owned by this list, and
+            // `&mut self` means nothing else is looking at it.
+            Some(mut head) => unsafe { head.as_mut().prev = Some(node) },
+            None => self.tail = Some(node),
+        }
+
+        self.head = Some(node);
+        self.len += 1;
+    }
+
+    fn push_back(&mut self, element: T) {
+        let node = Self::allocate(element, self.tail, None);
+        match self.tail {
+            // SAFETY: as in `push_front`.
+            Some(mut tail) => unsafe { tail.as_mut().next = Some(node) },
+            None => self.head = Some(node),
+        }
+
+        self.tail = Some(node);
+        self.len += 1;
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        let head = self.head?;
+        // SAFETY: `head` is live and owned by this list. Once the list stops
+        // pointing at it, it is unreachable and can be freed.
+        unsafe {
+            self.head = head.as_ref().next;
+            match self.head {
+                Some(mut next) => next.as_mut().prev = None,
+                None => self.tail = None,
+            }
+
+            self.len -= 1;
+            Some(Self::unlink(head))
+        }
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        let tail = self.tail?;
+        // SAFETY: as in `pop_front`.
+        unsafe {
+            self.tail = tail.as_ref().prev;
+            match self.tail {
+                Some(mut prev) => prev.as_mut().next = None,
+                None => self.head = None,
+            }
+
+            self.len -= 1;
+            Some(Self::unlink(tail))
+        }
+    }
+
+    fn front(&self) -> Option<&T> {
+        // SAFETY: the node lives as long as the list keeps it, and `&self`
+        // stops the list from popping it while the reference is held.
+        self.head.map(|head| unsafe { &(*head.as_ptr()).element })
+    }
+
+    fn back(&self) -> Option<&T> {
+        // SAFETY: as in `front`.
+        self.tail.map(|tail| unsafe { &(*tail.as_ptr()).element })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            head: self.head,
+            tail: self.tail,
+            len: self.len,
+            marker: PhantomData,
+        }
+    }
+
+    fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            head: self.head,
+            tail: self.tail,
+            len: self.len,
+            marker: PhantomData,
+        }
+    }
+
+    fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+
+        // SAFETY: as in `front`.
+        Some(unsafe { &(*self.node_at(index).as_ptr()).element })
+    }
+
+    fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len {
+            return None;
+        }
+
+        // SAFETY: as in `front`, with `&mut self` making the borrow unique.
+        Some(unsafe { &mut (*self.node_at(index).as_ptr()).element })
+    }
+
+    /// Closes the list into a ring and cuts it again before the new front,
+    /// so no element moves and nothing is allocated.
+    fn rotate_left(&mut self, n: usize) {
+        assert!(n <= self.len, "rotated by more than the length");
+        if n == 0 || n == self.len {
+            return;
+        }
+
+        let mut front = self.node_at(n);
+        // SAFETY: the list has at least two nodes, all live and owned by it,
+        // and `front` isn't the head so it has a previous node.
+        unsafe {
+            let (mut head, mut tail) = (self.head.unwrap(), self.tail.unwrap());
+            tail.as_mut().next = Some(head);
+            head.as_mut().prev = Some(tail);
+
+            let mut back = front.as_ref().prev.unwrap();
+            back.as_mut().next = None;
+            front.as_mut().prev = None;
+            self.head = Some(front);
+            self.tail = Some(back);
+        }
+    }
+}
+
+/// Pops one node at a time, so a long list can't overflow the stack the way
+/// a chain of boxes dropping each other would.
+impl<T> Drop for DequeLinkedList<T> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+impl<T> IntoIterator for DequeLinkedList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { list: self }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a DequeLinkedList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut DequeLinkedList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+/// Borrows the elements front to back. `head` and `tail` close in on each
+/// other, and `len` says when they have met.
+pub struct Iter<'a, T> {
+    head: Option<NonNull<ListNode<T>>>,
+    tail: Option<NonNull<ListNode<T>>>,
+    len: usize,
+    marker: PhantomData<&'a ListNode<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        // SAFETY: `len` nodes remain from `head`, all kept alive by the
+        // borrowed list.
+        let node = unsafe { &*self.head?.as_ptr() };
+        self.head = node.next;
+        self.len -= 1;
+        Some(&node.element)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        // SAFETY: as in `next`, counting back from `tail`.
+        let node = unsafe { &*self.tail?.as_ptr() };
+        self.tail = node.prev;
+        self.len -= 1;
+        Some(&node.element)
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+/// Mutably borrows the elements front to back.
+pub struct IterMut<'a, T> {
+    head: Option<NonNull<ListNode<T>>>,
+    tail: Option<NonNull<ListNode<T>>>,
+    len: usize,
+    marker: PhantomData<&'a mut ListNode<T>>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        // SAFETY: as for `Iter`, and `len` stops the two ends from handing
+        // out the same node twice.
+        let node = unsafe { &mut *self.head?.as_ptr() };
+        self.head = node.next;
+        self.len -= 1;
+        Some(&mut node.element)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        // SAFETY: as in `next`.
+        let node = unsafe { &mut *self.tail?.as_ptr() };
+        self.tail = node.prev;
+        self.len -= 1;
+        Some(&mut node.element)
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+/// Moves the elements out front to back. Whatever is left is dropped with
+/// the iterator.
+pub struct IntoIter<T> {
+    list: DequeLinkedList<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.list.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> FusedIterator for IntoIter<T> {}
+
+// SAFETY: the list owns its elements outright, like a `Vec` or `Box` would,
+// so sending or sharing it is as safe as sending or sharing them.
+unsafe impl<T: Send> Send for DequeLinkedList<T> {}
+unsafe impl<T: Sync> Sync for DequeLinkedList<T> {}
+
+// SAFETY: the iterators hand out references to the elements exactly as
+// `&DequeLinkedList` and `&mut DequeLinkedList` would.
+unsafe impl<T: Sync> Send for Iter<'_, T> {}
+unsafe impl<T: Sync> Sync for Iter<'_, T> {}
+unsafe impl<T: Send> Send for IterMut<'_, T> {}
+unsafe impl<T: Sync> Sync for IterMut<'_, T> {}
+
+#[cfg(test)]
+mod test {
+    use super::DequeLinkedList;
+    use crate::Deque;
+
+    type UDeque = DequeLinkedList<usize>;
+
+    #[test]
+    fn test_push_front_empty() {
+        let mut deque = UDeque::new();
+        deque.push_front(255);
+        assert_eq!(deque.back(), Some(&255));
+        assert_eq!(deque.front(), Some(&255));
+    }
+
+    #[test]
+    fn test_push_front_filled() {
+        let mut deque = UDeque::from_vec(vec![1, 2, 3]);
+        deque.push_front(255);
+        assert_eq!(deque.front(), Some(&255));
+        assert_eq!(deque.back(), Some(&3))
+    }
+
+    #[test]
+    fn test_push_back_empty() {
+        let mut deque = UDeque::new();
+        deque.push_back(255);
+        assert_eq!(deque.back(), Some(&255));
+        assert_eq!(deque.front(), Some(&255));
+    }
+
+    #[test]
+    fn test_push_back_filled() {
+        let mut deque = UDeque::from_vec(vec![1, 2, 3]);
+        deque.push_back(255);
+        assert_eq!(deque.front(), Some(&1));
+        assert_eq!(deque.back(), Some(&255))
+    }
+
+    #[test]
+    fn test_pop_front_empty() {
+        let mut deque = UDeque::new();
+        let elem = deque.pop_front();
+        assert!(elem.is_none());
+        assert!(deque.front().is_none());
+        assert!(deque.back().is_none());
+    }
+
+    #[test]
+    fn test_pop_front_one_element() {
+        let mut deque = UDeque::from_vec(vec![1]);
+        let elem = deque.pop_front();
+        assert_eq!(elem, Some(1));
+        assert!(deque.front().is_none());
+        assert!(deque.back().is_none());
+    }
+
+    #[test]
+    fn test_pop_front_filled() {
+        let mut deque = UDeque::from_vec(vec![1, 2, 3]);
+        let elem = deque.pop_front();
+        assert_eq!(elem, Some(1));
+        assert_eq!(deque.front(), Some(&2));
+        assert_eq!(deque.back(), Some(&3));
+    }
+
+    #[test]
+    fn test_pop_back_empty() {
+        let mut deque = UDeque::new();
+        let elem = deque.pop_back();
+        assert!(elem.is_none());
+        assert!(deque.front().is_none());
+        assert!(deque.back().is_none());
+    }
+
+    #[test]
+    fn test_pop_back_one_element() {
+        let mut deque = UDeque::from_vec(vec![1]);
+        let elem = deque.pop_back();
+        assert_eq!(elem, Some(1));
+        assert!(deque.front().is_none());
+        assert!(deque.back().is_none());
+    }
+
+    #[test]
+    fn test_pop_back_filled() {
+        let mut deque = UDeque::from_vec(vec![1, 2, 3]);
+        let elem = deque.pop_back();
+        assert_eq!(elem, Some(3));
+        assert_eq!(deque.front(), Some(&1));
+        assert_eq!(deque.back(), Some(&2));
+    }
+
+    #[test]
+    fn test_front_empty() {
+        let deque = UDeque::new();
+        assert!(deque.front().is_none());
+    }
+
+    #[test]
+    fn test_front_filled() {
+        let deque = UDeque::from_vec(vec![1, 2, 3]);
+        assert_eq!(deque.front(), Some(&1));
+    }
+
+    #[test]
+    fn test_back_empty() {
+        let deque = UDeque::new();
+        assert!(deque.back().is_none());
+    }
+
+    #[test]
+    fn test_back_filled() {
+        let deque = UDeque::from_vec(vec![1, 2, 3]);
+        assert_eq!(deque.back(), Some(&3));
+    }
+
+    #[test]
+    fn test_empty_empty() {
+        let deque = UDeque::new();
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn test_empty_filled() {
+        let deque = UDeque::from_vec(vec![1, 2, 3]);
+        assert!(!deque.is_empty());
+    }
+
+    #[test]
+    fn test_size_empty() {
+        let deque = UDeque::new();
+        assert_eq!(deque.len(), 0);
+    }
+
+    #[test]
+    fn test_size_filled() {
+        let deque = UDeque::from_vec(vec![1, 2, 3]);
+        assert_eq!(deque.len(), 3);
+    }
+
+    #[test]
+    fn test_alternating_ends() {
+        let mut deque = UDeque::new();
+        for value in 0..100 {
+            match value % 2 {
+                0 => deque.push_front(value),
+                _ => deque.push_back(value),
+            }
+        }
+        assert_eq!(deque.len(), 100);
+        assert_eq!(deque.front(), Some(&98));
+        assert_eq!(deque.back(), Some(&99));
+
+        for _ in 0..50 {
+            deque.pop_back();
+        }
+        assert_eq!(deque.back(), Some(&0));
+        assert_eq!(deque.pop_front(), Some(98));
+        assert_eq!(deque.len(), 49);
+    }
+
+    #[test]
+    fn test_elements_do_not_move() {
+        let mut deque = UDeque::new();
+        deque.push_back(1);
+        let address: *const usize = deque.front().unwrap();
+        for value in 2..1000 {
+            deque.push_back(value);
+            deque.push_front(value);
+        }
+        for _ in 2..1000 {
+            deque.pop_front();
+        }
+        assert!(std::ptr::eq(address, deque.front().unwrap()));
+    }
+
+    #[test]
+    fn test_drop_long_list() {
+        let mut deque = UDeque::new();
+        for value in 0..1_000_000 {
+            deque.push_back(value);
+        }
+        drop(deque);
+    }
+
+    #[test]
+    fn test_drops_every_element() {
+        use std::rc::Rc;
+
+        let counter = Rc::new(());
+        let mut deque = DequeLinkedList::new();
+        for _ in 0..10 {
+            deque.push_back(Rc::clone(&counter));
+        }
+        deque.pop_front();
+        assert_eq!(Rc::strong_count(&counter), 10);
+        drop(deque);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    /// Sums any deque through the trait alone.
+    fn sum<D: Deque<usize>>(deque: &D) -> usize {
+        deque.iter().sum()
+    }
+
+    #[test]
+    fn test_iter() {
+        let deque = UDeque::from_vec(vec![1, 3, 4, 5]);
+        let mut iter = deque.iter();
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&5));
+        assert_eq!(iter.next_back(), Some(&4));
+        assert_eq!(iter.len(), 1);
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        assert_eq!(deque.iter().rev().collect::<Vec<_>>(), [&5, &4, &3, &1]);
+        assert_eq!(sum(&deque), 13);
+        assert_eq!(UDeque::new().iter().next(), None);
+    }
+
+    #[test]
+    fn test_iter_mut() {
+        let mut deque = UDeque::from_vec(vec![1, 2, 3]);
+        deque.push_front(0);
+        for value in deque.iter_mut() {
+            *value *= 10;
+        }
+        if let Some(value) = deque.iter_mut().next_back() {
+            *value += 1;
+        }
+        for value in &mut deque {
+            *value += 1;
+        }
+        assert_eq!(deque.iter_mut().len(), 4);
+        assert_eq!(deque.into_iter().collect::<Vec<_>>(), [1, 11, 21, 32]);
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let mut deque = UDeque::from_vec(vec![2, 3]);
+        deque.push_front(1);
+        let mut iter = deque.into_iter();
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next_back(), Some(3));
+        assert_eq!(iter.collect::<Vec<_>>(), [1, 2]);
+
+        let mut values = vec![];
+        for value in UDeque::from_vec(vec![4, 5]) {
+            values.push(value);
+        }
+        assert_eq!(values, [4, 5]);
+    }
+
+    #[test]
+    fn test_into_iter_drops_rest() {
+        use std::rc::Rc;
+
+        let counter = Rc::new(());
+        let mut deque = DequeLinkedList::new();
+        for _ in 0..5 {
+            deque.push_back(Rc::clone(&counter));
+        }
+        let mut iter = deque.into_iter();
+        iter.next();
+        assert_eq!(Rc::strong_count(&counter), 5);
+        drop(iter);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn test_get() {
+        let mut deque = UDeque::from_vec(vec![1, 2, 3]);
+        assert_eq!(deque.get(0), Some(&1));
+        assert_eq!(deque.get(2), Some(&3));
+        assert_eq!(deque.get(3), None);
+        *deque.get_mut(1).unwrap() = 20;
+        assert!(deque.get_mut(3).is_none());
+        assert_eq!(deque.into_iter().collect::<Vec<_>>(), [1, 20, 3]);
+    }
+
+    #[test]
+    fn test_swap() {
+        let mut deque = UDeque::from_vec(vec![1, 2, 3, 4]);
+        deque.swap(0, 3);
+        deque.swap(2, 1);
+        deque.swap(1, 1);
+        assert_eq!(deque.into_iter().collect::<Vec<_>>(), [4, 3, 2, 1]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_swap_out_of_bounds() {
+        UDeque::from_vec(vec![1, 2]).swap(0, 2);
+    }
+
+    #[test]
+    fn test_rotate() {
+        let mut deque = UDeque::from_vec((0..10).collect());
+        let addresses: Vec<*const usize> =
+            deque.iter().map(|v| v as _).collect();
+        deque.rotate_left(3);
+        assert_eq!(
+            deque.iter().copied().collect::<Vec<_>>(),
+            [3, 4, 5, 6, 7, 8, 9, 0, 1, 2]
+        );
+        deque.rotate_right(3);
+        assert_eq!(
+            deque.iter().copied().collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]
+        );
+        deque.rotate_left(10);
+        assert_eq!(deque.front(), Some(&0));
+        // Rotating relinks the nodes without moving the values.
+        assert!(deque.iter().zip(addresses).all(|(v, a)| std::ptr::eq(v, a)));
+    }
+
+    fn create_filled(len: usize) -> UDeque {
+        UDeque::from_vec((0..len).collect())
+    }
+
+    fn values(deque: UDeque) -> Vec<usize> {
+        deque.into_iter().collect()
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut deque = create_filled(10);
+        deque.insert(2, 100);
+        deque.insert(9, 200);
+        deque.insert(0, 300);
+        deque.insert(13, 400);
+        assert_eq!(
+            values(deque),
+            [300, 0, 1, 100, 2, 3, 4, 5, 6, 7, 200, 8, 9, 400]
+        );
+
+        let mut deque = UDeque::new();
+        deque.insert(0, 1);
+        deque.insert(0, 0);
+        assert_eq!(values(deque), [0, 1]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_insert_out_of_bounds() {
+        create_filled(1).insert(2, 0);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut deque = create_filled(10);
+        assert_eq!(deque.remove(2), Some(2));
+        assert_eq!(deque.remove(6), Some(7));
+        assert_eq!(deque.remove(0), Some(0));
+        assert_eq!(deque.remove(6), Some(9));
+        assert_eq!(deque.remove(6), None);
+        assert_eq!(deque.back(), Some(&8));
+        deque.push_front(100);
+        deque.push_back(200);
+        assert_eq!(values(deque), [100, 1, 3, 4, 5, 6, 8, 200]);
+    }
+
+    #[test]
+    fn test_split_off_append() {
+        let mut deque = create_filled(10);
+        let address: *const usize = deque.get(7).unwrap();
+        let mut back = deque.split_off(6);
+        assert_eq!(deque.len(), 6);
+        assert_eq!(deque.back(), Some(&5));
+        assert_eq!(back.iter().copied().collect::<Vec<_>>(), [6, 7, 8, 9]);
+        assert!(std::ptr::eq(address, back.get(1).unwrap()));
+        assert!(deque.split_off(6).is_empty());
+
+        back.append(&mut deque);
+        assert!(deque.is_empty());
+        assert!(deque.front().is_none());
+        let mut empty = UDeque::new();
+        empty.append(&mut back);
+        assert!(back.is_empty());
+        assert_eq!(empty.len(), 10);
+        assert!(std::ptr::eq(address, empty.get(1).unwrap()));
+        assert_eq!(values(empty), [6, 7, 8, 9, 0, 1, 2, 3, 4, 5]);
+
+        let mut deque = create_filled(3);
+        assert_eq!(values(deque.split_off(0)), [0, 1, 2]);
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut deque = create_filled(10);
+        assert_eq!(deque.drain(1..3).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(deque.drain(5..=6).collect::<Vec<_>>(), [7, 8]);
+        assert_eq!(deque.drain(2..2).len(), 0);
+        deque.push_front(100);
+        assert_eq!(values(deque), [100, 0, 3, 4, 5, 6, 9]);
+
+        let mut deque = create_filled(10);
+        drop(deque.drain(..4));
+        assert_eq!(deque.drain(3..).collect::<Vec<_>>(), [7, 8, 9]);
+        deque.push_back(100);
+        assert_eq!(values(deque), [4, 5, 6, 100]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_drain_out_of_bounds() {
+        create_filled(10).drain(5..11);
+    }
+
+    #[test]
+    fn test_retain_truncate() {
+        let mut deque = create_filled(10);
+        deque.retain(|value| value % 3 != 0);
+        assert_eq!(
+            deque.iter().copied().collect::<Vec<_>>(),
+            [1, 2, 4, 5, 7, 8]
+        );
+        assert_eq!(deque.front(), Some(&1));
+        assert_eq!(deque.back(), Some(&8));
+        deque.truncate(10);
+        assert_eq!(deque.len(), 6);
+        deque.truncate(4);
+        assert_eq!(deque.back(), Some(&5));
+        assert_eq!(values(deque), [1, 2, 4, 5]);
+    }
+}
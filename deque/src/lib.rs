@@ -14,39 +14,228 @@
 //
 // You should have received a copy of the GNU General Public License
 // along with deque.  If not, see <https://www.gnu.org/licenses/>.
+//! Double-ended queues.
+//!
+//! The crate only needs `core`. The deques that allocate are behind the
+//! `alloc` feature, which is on by default. [`ArrayDeque`] is always there.
+#![cfg_attr(not(test), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use core::iter::FusedIterator;
+use core::mem;
+#[cfg(feature = "alloc")]
+use core::ops::{Bound, RangeBounds};
+
+pub mod array_deque;
+#[cfg(feature = "alloc")]
+pub mod bounded_deque;
+#[cfg(feature = "alloc")]
 pub mod deque_array_list;
+#[cfg(feature = "alloc")]
 pub mod deque_linked_list;
 
+pub use array_deque::ArrayDeque;
+#[cfg(feature = "alloc")]
+pub use bounded_deque::{BoundedDeque, OverflowPolicy};
+#[cfg(feature = "alloc")]
 pub use deque_array_list::DequeArrayList;
+#[cfg(feature = "alloc")]
 pub use deque_linked_list::DequeLinkedList;
 
-pub trait Deque<T> {
-    fn push_front(&mut self, element: Node<T>);
-    fn push_back(&mut self, element: Node<T>);
-    fn pop_front(&mut self) -> Option<Node<T>>;
-    fn pop_back(&mut self) -> Option<Node<T>>;
-    fn front(&self) -> Option<&Node<T>>;
-    fn back(&self) -> Option<&Node<T>>;
+/// Double-ended queue of plain values.
+///
+/// Every deque can be walked front to back, or back to front, by reference,
+/// by mutable reference or by value. Consuming a deque goes through its
+/// [`IntoIterator`] implementation, so it also works in a `for` loop.
+pub trait Deque<T>:
+    IntoIterator<
+    Item = T,
+    IntoIter: DoubleEndedIterator + ExactSizeIterator + FusedIterator,
+>
+{
+    type Iter<'a>: DoubleEndedIterator<Item = &'a T>
+        + ExactSizeIterator
+        + FusedIterator
+    where
+        Self: 'a,
+        T: 'a;
+    type IterMut<'a>: DoubleEndedIterator<Item = &'a mut T>
+        + ExactSizeIterator
+        + FusedIterator
+    where
+        Self: 'a,
+        T: 'a;
+
+    fn push_front(&mut self, element: T);
+    fn push_back(&mut self, element: T);
+    fn pop_front(&mut self) -> Option<T>;
+    fn pop_back(&mut self) -> Option<T>;
+    fn front(&self) -> Option<&T>;
+    fn back(&self) -> Option<&T>;
     fn is_empty(&self) -> bool;
     fn len(&self) -> usize;
+    fn iter(&self) -> Self::Iter<'_>;
+    fn iter_mut(&mut self) -> Self::IterMut<'_>;
+
+    /// Pushes onto the front, or hands `element` back if the deque has no
+    /// room for it. Deques that grow never refuse.
+    fn try_push_front(&mut self, element: T) -> Result<(), T> {
+        self.push_front(element);
+        Ok(())
+    }
+
+    /// Pushes onto the back, or hands `element` back if the deque has no
+    /// room for it. Deques that grow never refuse.
+    fn try_push_back(&mut self, element: T) -> Result<(), T> {
+        self.push_back(element);
+        Ok(())
+    }
+
+    /// The element `index` places from the front. Walks there from the
+    /// front unless the deque has a faster way.
+    fn get(&self, index: usize) -> Option<&T> {
+        self.iter().nth(index)
+    }
+
+    fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.iter_mut().nth(index)
+    }
+
+    /// Swaps the elements at `i` and `j`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either index is out of bounds.
+    fn swap(&mut self, i: usize, j: usize) {
+        assert!(i < self.len() && j < self.len(), "index out of bounds");
+        if i == j {
+            return;
+        }
+
+        let (low, high) = (i.min(j), i.max(j));
+        let mut iter = self.iter_mut();
+        let first = iter.nth(low).unwrap();
+        let second = iter.nth(high - low - 1).unwrap();
+        mem::swap(first, second);
+    }
+
+    /// Moves the first `n` elements to the back, keeping their order.
+    /// Takes O(min(n, len - n)) pushes and pops.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than the length.
+    fn rotate_left(&mut self, n: usize) {
+        assert!(n <= self.len(), "rotated by more than the length");
+        match n <= self.len() - n {
+            true => {
+                for _ in 0..n {
+                    let element = self.pop_front().unwrap();
+                    self.push_back(element);
+                }
+            },
+            false => {
+                for _ in 0..self.len() - n {
+                    let element = self.pop_back().unwrap();
+                    self.push_front(element);
+                }
+            },
+        }
+    }
+
+    /// Moves the last `n` elements to the front, keeping their order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than the length.
+    fn rotate_right(&mut self, n: usize) {
+        assert!(n <= self.len(), "rotated by more than the length");
+        self.rotate_left(self.len() - n);
+    }
+}
+
+/// Turns `range` into a start and end index into a deque of `len` elements.
+///
+/// # Panics
+///
+/// Panics if the range starts after it ends or ends after `len`.
+#[cfg(feature = "alloc")]
+fn index_range<R: RangeBounds<usize>>(range: R, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => {
+            start.checked_add(1).expect("range overflow")
+        },
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.checked_add(1).expect("range overflow"),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+    assert!(start <= end, "range starts at {start} but ends at {end}");
+    assert!(
+        end <= len,
+        "range end {end} is out of bounds for length {len}"
+    );
+
+    (start, end)
 }
 
+/// An element that may be empty.
+///
+/// The deques used to store every element as a `Node`. They hold plain
+/// values now, and need a `Node` only where an empty element means
+/// something. Code written against the old API can keep its element type
+/// by storing nodes, as in `DequeArrayList<Node<T>>`, until it moves to
+/// plain values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Node<T> {
     pub data: Option<T>,
 }
 
 impl<T> Node<T> {
-    fn new() -> Self {
+    pub fn new() -> Self {
         Self { data: None }
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.data.is_none()
+    }
+}
+
+impl<T> From<T> for Node<T> {
     fn from(data: T) -> Self {
         Self { data: Some(data) }
     }
 }
 
+impl<T> From<Option<T>> for Node<T> {
+    fn from(data: Option<T>) -> Self {
+        Self { data }
+    }
+}
+
+impl<T> From<Node<T>> for Option<T> {
+    fn from(node: Node<T>) -> Self {
+        node.data
+    }
+}
+
 impl<T> Default for Node<T> {
     fn default() -> Self {
         Self::new()
     }
 }
+
+/// Deque of [`Node`]s, the element type every deque used to be limited to.
+#[cfg(feature = "alloc")]
+#[deprecated(note = "store plain values in `DequeArrayList<T>` instead")]
+pub type NodeDequeArrayList<T> = DequeArrayList<Node<T>>;
+
+/// Deque of [`Node`]s, the element type every deque used to be limited to.
+#[cfg(feature = "alloc")]
+#[deprecated(note = "store plain values in `DequeLinkedList<T>` instead")]
+pub type NodeDequeLinkedList<T> = DequeLinkedList<Node<T>>;